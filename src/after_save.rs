@@ -0,0 +1,7 @@
+/// What to do once the active buffer has been saved, when saving was
+/// requested by closing the buffer or quitting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AfterSave {
+  Close,
+  Quit,
+}
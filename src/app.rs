@@ -1,475 +1,4769 @@
 use super::*;
 
+mod file_changes;
+mod keyboard;
+mod mouse;
+mod prompts;
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct App {
-  cursor_position: usize,
-  editor_content: Rope,
+  after_save: Option<AfterSave>,
+  auto_scrolled_at: Instant,
+  buffers: Vec<Buffer>,
+  click: Option<Click>,
+  clipboard: Box<dyn Clipboard>,
+  diff: Option<String>,
+  /// Lines of `diff` scrolled out of view above the pane.
+  diff_scroll: usize,
+  dragging_selection: bool,
+  dragging_tab: bool,
   error: Option<Error>,
+  exit: bool,
+  finder: Option<Finder>,
+  finder_generation: u64,
+  focus: u64,
+  /// Cursor area last reported to the input method.
+  ime_cursor_area: Option<(PhysicalPosition<f32>, PhysicalSize<f32>)>,
+  layout: Split,
+  metrics: Metrics,
+  modifiers: ModifiersState,
+  mouse_position: Option<PhysicalPosition<f64>>,
+  next_buffer_id: u64,
+  next_pane_id: u64,
+  panes: BTreeMap<u64, Pane>,
+  pipe: Option<Pipe>,
+  /// Text being composed with an input method, and the byte range of the
+  /// cursor within it, if it should be shown.
+  preedit: Option<(String, Option<(usize, usize)>)>,
+  prompt: Option<Prompt>,
+  proxy: Option<EventLoopProxy<UserEvent>>,
+  recoveries: Vec<Recovery>,
   renderer: Option<Renderer>,
+  scratch_saved_at: Instant,
+  scroll_animated_at: Instant,
+  scroll_margin: usize,
+  size: PhysicalSize<u32>,
+  status: Option<String>,
+  swap: Option<Swap>,
+  watcher: Option<Watcher>,
   window: Option<Arc<Window>>,
+  word_separators: String,
 }
 
 impl App {
   pub fn new() -> Self {
     Self {
-      cursor_position: 0,
-      editor_content: Rope::new(),
+      after_save: None,
+      auto_scrolled_at: Instant::now(),
+      buffers: vec![Buffer::new(0)],
+      click: None,
+      clipboard: Box::new(MemoryClipboard::default()),
+      diff: None,
+      diff_scroll: 0,
+      dragging_selection: false,
+      dragging_tab: false,
       error: None,
+      exit: false,
+      finder: None,
+      finder_generation: 0,
+      focus: 0,
+      ime_cursor_area: None,
+      layout: Split::Leaf(0),
+      metrics: Metrics::default(),
+      modifiers: ModifiersState::empty(),
+      mouse_position: None,
+      next_buffer_id: 1,
+      next_pane_id: 1,
+      panes: BTreeMap::from([(
+        0,
+        Pane {
+          anchor: None,
+          buffer: 0,
+          cursor: 0,
+          momentum: Momentum::default(),
+          scroll: 0,
+          scroll_column: 0,
+          scroll_offset: (0.0, 0.0),
+        },
+      )]),
+      pipe: None,
+      preedit: None,
+      prompt: None,
+      proxy: None,
+      recoveries: Vec::new(),
       renderer: None,
+      scratch_saved_at: Instant::now(),
+      scroll_animated_at: Instant::now(),
+      scroll_margin: Pane::SCROLL_MARGIN,
+      size: PhysicalSize {
+        width: 1600,
+        height: 1200,
+      },
+      status: None,
+      swap: None,
+      watcher: None,
       window: None,
+      word_separators: WORD_SEPARATORS.into(),
     }
   }
 
-  pub fn error(self) -> Option<Error> {
-    self.error
+  pub fn open(path: PathBuf) -> Result<Self> {
+    let mut app = Self::new();
+    app.open_file(path)?;
+    Ok(app)
   }
 
-  fn resize(&mut self, new_size: PhysicalSize<u32>) {
-    if new_size.width > 0 && new_size.height > 0 {
-      if let Some(renderer) = &mut self.renderer {
-        renderer.resize(new_size);
-      }
+  pub fn scratch(scratch: Scratch) -> Result<Self> {
+    Ok(Self {
+      buffers: vec![Buffer::scratch(0, scratch)?],
+      ..Self::new()
+    })
+  }
+
+  /// Read the buffer from `input`, typically stdin, to be written to stdout
+  /// when committed.
+  pub fn pipe(mut input: impl Read) -> Result<Self> {
+    let mut bytes = Vec::new();
+
+    input.read_to_end(&mut bytes).context(error::ReadStdin)?;
+
+    let encoding = Encoding::detect(&bytes);
+
+    let decoded = encoding.decode(&bytes);
+
+    let hint = "Ctrl+Enter to commit, Escape to cancel";
+
+    let status = if decoded.lossy {
+      format!(
+        "warning: stdin is not valid {encoding}, invalid bytes were replaced. \
+         {hint}"
+      )
+    } else {
+      hint.into()
+    };
+
+    Ok(Self {
+      buffers: vec![Buffer {
+        stdin: true,
+        ..Buffer::with_content(0, Rope::from_str(&decoded.text), encoding)
+      }],
+      pipe: Some(Pipe::Open),
+      status: Some(status),
+      ..Self::new()
+    })
+  }
+
+  /// Consume the app once the event loop has exited, returning the error
+  /// that stopped it, if any, or in pipe mode, the committed output.
+  pub fn finish(self) -> Result<Option<Vec<u8>>> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
+
+    match self.pipe {
+      Some(Pipe::Cancelled) => Err(error::Cancelled.build()),
+      Some(Pipe::Committed(output)) => Ok(Some(output)),
+      Some(Pipe::Open) | None => Ok(None),
     }
   }
 
-  fn render(&mut self) -> Result {
-    if let Some(renderer) = &mut self.renderer {
-      let text_content = self.editor_content.to_string();
-      renderer.render(&text_content, self.cursor_position)?;
+  /// Index of the buffer shown in the focused pane.
+  fn active(&self) -> usize {
+    self
+      .buffers
+      .iter()
+      .position(|buffer| buffer.id == self.panes[&self.focus].buffer)
+      .unwrap_or_default()
+  }
+
+  fn buffer(&self) -> &Buffer {
+    &self.buffers[self.active()]
+  }
+
+  fn buffer_mut(&mut self) -> &mut Buffer {
+    let active = self.active();
+    &mut self.buffers[active]
+  }
+
+  fn pane_mut(&mut self) -> &mut Pane {
+    self
+      .panes
+      .get_mut(&self.focus)
+      .expect("focused pane should exist")
+  }
+
+  fn new_buffer_id(&mut self) -> u64 {
+    let id = self.next_buffer_id;
+    self.next_buffer_id += 1;
+    id
+  }
+
+  /// Open the file at `path` in a new buffer, or switch to its buffer if it
+  /// is already open.
+  pub fn open_file(&mut self, path: PathBuf) -> Result {
+    if let Some(index) = self
+      .buffers
+      .iter()
+      .position(|buffer| buffer.path.as_ref() == Some(&path))
+    {
+      self.activate(index);
+      return Ok(());
+    }
+
+    let disk_stamp = Stamp::of(&path);
+
+    let (encoding, decoded) = Buffer::read_file(&path)?.unwrap_or_default();
+
+    if decoded.lossy {
+      self.status = Some(format!(
+        "warning: `{}` is not valid {encoding}, invalid bytes were replaced",
+        path.display()
+      ));
     }
 
+    let id = self.new_buffer_id();
+
+    let history = History::load(&path, &decoded.text).unwrap_or_default();
+
+    self.add_buffer(Buffer {
+      disk_stamp,
+      history,
+      path: Some(path),
+      ..Buffer::with_content(id, Rope::from_str(&decoded.text), encoding)
+    });
+
     Ok(())
   }
 
-  fn handle_keyboard_input(&mut self, key: Key, state: ElementState) {
-    if state == ElementState::Pressed {
-      match key {
-        Key::Named(NamedKey::Backspace) => {
-          if self.cursor_position > 0 {
-            self
-              .editor_content
-              .remove(self.cursor_position - 1..self.cursor_position);
-            self.cursor_position -= 1;
-          }
-        }
-        Key::Named(NamedKey::Delete) => {
-          if self.cursor_position < self.editor_content.len_chars() {
-            self
-              .editor_content
-              .remove(self.cursor_position..self.cursor_position + 1);
-          }
-        }
-        Key::Named(NamedKey::ArrowLeft) => {
-          if self.cursor_position > 0 {
-            self.cursor_position -= 1;
-          }
-        }
-        Key::Named(NamedKey::ArrowRight) => {
-          if self.cursor_position < self.editor_content.len_chars() {
-            self.cursor_position += 1;
-          }
-        }
-        Key::Named(NamedKey::Home) => {
-          self.cursor_position = 0;
-        }
-        Key::Named(NamedKey::End) => {
-          self.cursor_position = self.editor_content.len_chars();
-        }
-        Key::Named(NamedKey::Enter) => {
-          self.editor_content.insert(self.cursor_position, "\n");
-          self.cursor_position += 1;
-        }
-        Key::Named(NamedKey::Space) => {
-          self.editor_content.insert(self.cursor_position, " ");
-          self.cursor_position += 1;
-        }
-        Key::Character(c) => {
-          self.editor_content.insert(self.cursor_position, &c);
-          self.cursor_position += c.len();
-        }
-        _ => {}
-      }
+  /// Add `buffer` and switch to it, replacing the active buffer if it is
+  /// pristine.
+  fn add_buffer(&mut self, buffer: Buffer) {
+    if let Some(watcher) = &self.watcher
+      && let Some(path) = &buffer.path
+    {
+      watcher.watch(path);
+    }
+
+    if self.buffer().is_pristine() {
+      // Keep the ID, which panes use to refer to the buffer.
+      let id = self.buffer().id;
+      *self.buffer_mut() = Buffer { id, ..buffer };
+      self.update_title();
+    } else {
+      self.buffers.push(buffer);
+      self.activate(self.buffers.len() - 1);
     }
   }
-}
 
-impl ApplicationHandler for App {
-  fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-    if self.window.is_none() {
-      let window = match event_loop
-        .create_window(
-          WindowAttributes::default()
-            .with_inner_size(PhysicalSize {
-              width: 1600,
-              height: 1200,
-            })
-            .with_min_inner_size(PhysicalSize {
-              width: 800,
-              height: 600,
+  fn new_buffer(&mut self) {
+    let id = self.new_buffer_id();
+    self.buffers.push(Buffer::new(id));
+    self.activate(self.buffers.len() - 1);
+  }
+
+  /// Show the buffer at `index` in the focused pane.
+  fn activate(&mut self, index: usize) {
+    let id = self.buffers[index].id;
+
+    let pane = self.pane_mut();
+
+    if pane.buffer != id {
+      pane.buffer = id;
+      pane.reset_scroll();
+      self.scroll_to_cursor();
+    }
+
+    self.update_title();
+  }
+
+  /// Move the active buffer to `index`, as when dragging its tab.
+  fn move_buffer(&mut self, index: usize) {
+    let buffer = self.buffers.remove(self.active());
+    self.buffers.insert(index, buffer);
+  }
+
+  fn next_buffer(&mut self) {
+    self.activate((self.active() + 1) % self.buffers.len());
+  }
+
+  fn previous_buffer(&mut self) {
+    self
+      .activate((self.active() + self.buffers.len() - 1) % self.buffers.len());
+  }
+
+  /// Switch to the buffer called `name`, or failing that, the only buffer
+  /// whose name or path contains it.
+  fn switch_buffer(&mut self, name: &str) {
+    let name = name.trim();
+
+    let mut matches = (0..self.buffers.len())
+      .filter(|&index| self.buffers[index].name() == name)
+      .collect::<Vec<usize>>();
+
+    if matches.is_empty() {
+      let needle = name.to_lowercase();
+
+      matches = (0..self.buffers.len())
+        .filter(|&index| {
+          let buffer = &self.buffers[index];
+
+          buffer.name().to_lowercase().contains(&needle)
+            || buffer.path.as_ref().is_some_and(|path| {
+              path.display().to_string().to_lowercase().contains(&needle)
             })
-            .with_title(env!("CARGO_PKG_NAME")),
-        )
-        .context(error::CreateWindow)
-      {
-        Ok(window) => Arc::new(window),
-        Err(err) => {
-          self.error = Some(err);
-          event_loop.exit();
-          return;
-        }
-      };
+        })
+        .collect();
+    }
 
-      let window_clone = window.clone();
+    match matches.as_slice() {
+      [index] => self.activate(*index),
+      [] => self.status = Some(format!("no buffer matches `{name}`")),
+      _ => {
+        self.status =
+          Some(format!("`{name}` matches {} buffers", matches.len()));
+      }
+    }
+  }
 
-      let future = async move { Renderer::new(window_clone).await };
+  /// Close the active buffer, asking what to do with any unsaved changes
+  /// first.
+  fn close_buffer(&mut self) {
+    if self.buffer().stdin {
+      self.status = Some(
+        "stdin cannot be closed: Ctrl+Enter to commit, Escape to cancel".into(),
+      );
+      return;
+    }
 
-      match pollster::block_on(future) {
-        Ok(renderer) => {
-          self.renderer = Some(renderer);
-          self.window = Some(window);
-        }
-        Err(err) => {
-          self.error = Some(err);
-          event_loop.exit();
-          return;
-        }
-      };
+    if let Err(error) = self.save_scratch() {
+      self.status = Some(error.summary());
+    }
 
-      if let Some(window) = &self.window {
-        window.request_redraw();
+    if self.buffer().is_modified() {
+      self.after_save = Some(AfterSave::Close);
+      self.prompt = Some(Prompt::new(PromptKind::UnsavedChanges, ""));
+    } else {
+      self.remove_buffer();
+    }
+  }
+
+  /// Drop the active buffer, along with its swap file, leaving an untitled
+  /// buffer in its place if it was the last one.
+  fn remove_buffer(&mut self) {
+    let index = self.active();
+
+    let buffer = self.buffers.remove(index);
+
+    if let Some(watcher) = &self.watcher
+      && let Some(path) = &buffer.path
+    {
+      watcher.unwatch(path);
+    }
+
+    if let Some(swap) = &self.swap
+      && buffer.swap_revision.is_some()
+    {
+      swap.remove(&swap.path(buffer.id));
+    }
+
+    if self.buffers.is_empty() {
+      let id = self.new_buffer_id();
+      self.buffers.push(Buffer::new(id));
+    }
+
+    let replacement = &self.buffers[index.min(self.buffers.len() - 1)];
+
+    for pane in self.panes.values_mut() {
+      if pane.buffer == buffer.id {
+        pane.anchor = None;
+        pane.buffer = replacement.id;
+        pane.cursor = replacement.cursor;
+        pane.reset_scroll();
       }
     }
+
+    self.scroll_to_cursor();
+    self.update_title();
   }
 
-  fn window_event(
+  /// Move focus to `pane`, saving the cursor and selection of the pane
+  /// losing focus and restoring those of the pane gaining it.
+  fn focus_pane(&mut self, pane: u64) {
+    if pane == self.focus {
+      return;
+    }
+
+    let buffer = self.buffer_mut();
+
+    // Other cursors are kept only while the pane has focus.
+    buffer.cursors.clear();
+
+    let (anchor, cursor) = (buffer.anchor, buffer.cursor);
+
+    if let Some(unfocused) = self.panes.get_mut(&self.focus) {
+      unfocused.anchor = anchor;
+      unfocused.cursor = cursor;
+    }
+
+    self.focus = pane;
+    self.restore_cursor();
+  }
+
+  /// Move the active buffer's cursor and selection to where the focused pane
+  /// left them.
+  fn restore_cursor(&mut self) {
+    let Pane { anchor, cursor, .. } = self.panes[&self.focus];
+
+    let buffer = self.buffer_mut();
+
+    let len = buffer.content.len_chars();
+
+    buffer.anchor = anchor.map(|anchor| anchor.min(len));
+    buffer.cursor = cursor.min(len);
+    buffer.cursors.clear();
+    buffer.goal = None;
+
+    self.scroll_to_cursor();
+    self.update_title();
+  }
+
+  /// Split the focused pane in two, both showing its buffer, and focus the
+  /// new pane.
+  fn split_pane(&mut self, orientation: Orientation) {
+    let id = self.next_pane_id;
+
+    self.next_pane_id += 1;
+
+    let pane = Pane {
+      anchor: self.buffer().anchor,
+      cursor: self.buffer().cursor,
+      ..self.panes[&self.focus].clone()
+    };
+
+    self.panes.insert(id, pane);
+    self.layout.split(self.focus, orientation, id);
+    self.focus_pane(id);
+  }
+
+  fn close_pane(&mut self) {
+    let Some(next) = self.layout.remove(self.focus) else {
+      self.status = Some("cannot close the only pane".into());
+      return;
+    };
+
+    self.panes.remove(&self.focus);
+    self.focus = next;
+    self.restore_cursor();
+  }
+
+  /// Focus the nearest pane in `direction` from the focused pane.
+  fn focus_neighbor(&mut self, direction: Direction) {
+    let rects = self.layout.layout(self.text_area());
+
+    let Some(&(_, current)) = rects.iter().find(|(id, _)| *id == self.focus)
+    else {
+      return;
+    };
+
+    let overlap = |a: (f32, f32), b: (f32, f32)| a.0.max(b.0) < a.1.min(b.1);
+
+    let neighbor = rects
+      .iter()
+      .filter(|(id, _)| *id != self.focus)
+      .filter_map(|&(id, rect)| {
+        let distance = match direction {
+          Direction::Down => rect.y - current.bottom(),
+          Direction::Left => current.x - rect.right(),
+          Direction::Right => rect.x - current.right(),
+          Direction::Up => current.y - rect.bottom(),
+        };
+
+        let adjacent = match direction {
+          Direction::Down | Direction::Up => {
+            overlap((rect.x, rect.right()), (current.x, current.right()))
+          }
+          Direction::Left | Direction::Right => {
+            overlap((rect.y, rect.bottom()), (current.y, current.bottom()))
+          }
+        };
+
+        (adjacent && distance >= 0.0).then_some((id, distance))
+      })
+      .min_by(|a, b| a.1.total_cmp(&b.1))
+      .map(|(id, _)| id);
+
+    if let Some(neighbor) = neighbor {
+      self.focus_pane(neighbor);
+    }
+  }
+
+  /// Move the divider next to the focused pane in `direction`.
+  fn resize_pane(&mut self, direction: Direction) {
+    let (orientation, delta) = match direction {
+      Direction::Down => (Orientation::Horizontal, 0.05),
+      Direction::Left => (Orientation::Vertical, -0.05),
+      Direction::Right => (Orientation::Vertical, 0.05),
+      Direction::Up => (Orientation::Horizontal, -0.05),
+    };
+
+    self.layout.resize(self.focus, orientation, delta);
+    self.scroll_to_cursor();
+  }
+
+  /// Area of the window below the tab bar and above the status line, which
+  /// is divided between panes.
+  fn text_area(&self) -> Rect {
+    Rect {
+      height: (self.size.height as f32 - Tab::HEIGHT - View::STATUS_HEIGHT)
+        .max(0.0),
+      width: self.size.width as f32,
+      x: 0.0,
+      y: Tab::HEIGHT,
+    }
+  }
+
+  /// Number of lines that fit in a pane of `rect`.
+  fn rows(&self, rect: Rect) -> usize {
+    ((rect.height - Pane::PADDING) / self.metrics.line_height).max(1.0) as usize
+  }
+
+  /// Number of columns that fit in a pane of `rect`.
+  fn columns(&self, rect: Rect) -> usize {
+    ((rect.width - 2.0 * Pane::PADDING) / self.metrics.advance).max(1.0)
+      as usize
+  }
+
+  /// Rectangle of the focused pane.
+  fn pane_rect(&self) -> Option<Rect> {
+    self
+      .layout
+      .layout(self.text_area())
+      .into_iter()
+      .find(|(id, _)| *id == self.focus)
+      .map(|(_, rect)| rect)
+  }
+
+  /// Scroll the focused pane just far enough to show the cursor, along with
+  /// up to `scroll_margin` lines and columns around it.
+  fn scroll_to_cursor(&mut self) {
+    self.scroll_to_cursor_with_margin(self.scroll_margin);
+  }
+
+  /// Scroll the focused pane just far enough to show the cursor, along with
+  /// up to `margin` lines and columns around it.
+  fn scroll_to_cursor_with_margin(&mut self, margin: usize) {
+    let Some(rect) = self.pane_rect() else {
+      return;
+    };
+
+    let (rows, columns) = (self.rows(rect), self.columns(rect));
+
+    // Shrink the margin in small panes so that the cursor can still move
+    // without scrolling.
+    let (line_margin, column_margin) =
+      (margin.min((rows - 1) / 2), margin.min((columns - 1) / 2));
+
+    let buffer = self.buffer();
+
+    let line = buffer.content.char_to_line(buffer.cursor);
+
+    let column = buffer.cursor - buffer.content.line_to_char(line);
+
+    let lines = line.saturating_sub(line_margin)
+      ..=(line + line_margin).min(buffer.content.len_lines() - 1);
+
+    let line_columns = column.saturating_sub(column_margin)
+      ..=(column + column_margin).min(buffer.line_len(line));
+
+    let pane = self.pane_mut();
+
+    let (partial_column, partial_line) =
+      (pane.scroll_offset.0 > 0.0, pane.scroll_offset.1 > 0.0);
+
+    if let Some(scroll) = Self::reveal(pane.scroll, partial_line, rows, lines) {
+      pane.momentum = Momentum::default();
+      pane.scroll = scroll;
+      pane.scroll_offset.1 = 0.0;
+    }
+
+    if let Some(scroll_column) =
+      Self::reveal(pane.scroll_column, partial_column, columns, line_columns)
+    {
+      pane.momentum = Momentum::default();
+      pane.scroll_column = scroll_column;
+      pane.scroll_offset.0 = 0.0;
+    }
+  }
+
+  /// The offset closest to `offset` at which a window of `size` shows all
+  /// of `range`, or its start if it does not fit, or `None` if the window
+  /// already shows it. When the window is scrolled `partial`ly into its
+  /// first line or column, that one does not count as shown.
+  fn reveal(
+    offset: usize,
+    partial: bool,
+    size: usize,
+    range: RangeInclusive<usize>,
+  ) -> Option<usize> {
+    let (first, shown) = if partial {
+      (offset + 1, size.saturating_sub(1))
+    } else {
+      (offset, size)
+    };
+
+    if *range.start() < first {
+      Some(*range.start())
+    } else if *range.end() >= first + shown {
+      Some((range.end() + 1).saturating_sub(size).min(*range.start()))
+    } else {
+      None
+    }
+  }
+
+  /// Scroll the focused pane by `lines` without moving the cursor, stopping
+  /// with the last line at the top.
+  fn scroll_by(&mut self, lines: isize) {
+    let last = self.buffer().content.len_lines() - 1;
+
+    let pane = self.pane_mut();
+
+    pane.momentum = Momentum::default();
+    pane.scroll = pane.scroll.saturating_add_signed(lines).min(last);
+    pane.scroll_offset.1 = 0.0;
+  }
+
+  /// Move the cursors and the view of the focused pane a page up or down,
+  /// keeping one line of the previous page in view.
+  fn page(&mut self, select: bool, down: bool) {
+    let Some(rect) = self.pane_rect() else {
+      return;
+    };
+
+    let page = self.rows(rect).saturating_sub(1).max(1) as isize;
+
+    let lines = if down { page } else { -page };
+
+    self.scroll_by(lines);
+    self.move_lines(select, lines);
+  }
+
+  /// Scroll the focused pane to put the cursor's line in the middle.
+  fn scroll_to_center(&mut self) {
+    let Some(rect) = self.pane_rect() else {
+      return;
+    };
+
+    let rows = self.rows(rect);
+
+    let buffer = self.buffer();
+
+    let line = buffer.content.char_to_line(buffer.cursor);
+
+    let pane = self.pane_mut();
+
+    pane.momentum = Momentum::default();
+    pane.scroll = line.saturating_sub(rows / 2);
+    pane.scroll_offset.1 = 0.0;
+  }
+
+  /// Width of `text` as drawn by the renderer, or in cells of
+  /// `metrics.advance` before there is one.
+  fn text_width(&self, text: &str) -> f32 {
+    match &self.renderer {
+      Some(renderer) => renderer.text_width(text, usize::MAX),
+      None => text.chars().count() as f32 * self.metrics.advance,
+    }
+  }
+
+  /// Insert `text` into the active buffer, moving the cursors of other panes
+  /// showing it to stay on the same text.
+  fn insert(&mut self, char_idx: usize, text: &str) {
+    self.buffer_mut().insert(char_idx, text);
+    self.shift_cursors(char_idx, 0, text.chars().count());
+  }
+
+  /// Remove `char_range` from the active buffer, moving the cursors of other
+  /// panes showing it to stay on the same text.
+  fn remove(&mut self, char_range: Range<usize>) {
+    self.buffer_mut().remove(char_range.clone());
+    self.shift_cursors(char_range.start, char_range.len(), 0);
+  }
+
+  /// Move the cursors and anchors of other panes showing the active buffer
+  /// after `removed` chars at `position` were replaced by `inserted` chars.
+  /// Those within the replaced text move to its start.
+  fn shift_cursors(
     &mut self,
-    event_loop: &ActiveEventLoop,
-    _id: WindowId,
-    event: WindowEvent,
+    position: usize,
+    removed: usize,
+    inserted: usize,
   ) {
-    match event {
-      WindowEvent::CloseRequested => {
-        event_loop.exit();
-      }
-      WindowEvent::Resized(new_size) => {
-        self.resize(new_size);
+    let end = position + removed;
+
+    let shift = |char_idx: usize| {
+      if char_idx > end || (removed > 0 && char_idx == end) {
+        char_idx - removed + inserted
+      } else {
+        char_idx.min(position)
       }
-      WindowEvent::KeyboardInput { event, .. } => {
-        if event.state == ElementState::Pressed {
-          match event.logical_key {
-            Key::Named(NamedKey::Escape) => {
-              event_loop.exit();
-            }
-            _ => {
-              self.handle_keyboard_input(event.logical_key, event.state);
+    };
 
-              if let Some(window) = &self.window {
-                window.request_redraw();
-              }
-            }
-          }
-        } else {
-          self.handle_keyboard_input(event.logical_key, event.state);
-        }
+    for pane in self.other_panes_mut() {
+      pane.anchor = pane.anchor.map(shift);
+      pane.cursor = shift(pane.cursor);
+    }
+  }
+
+  /// Replace text at every cursor of the active buffer, from first to last.
+  /// `edit` is given each selection, moved to account for the edits before
+  /// it, and returns the range to replace and the text to replace it with.
+  /// The cursor is left after the new text, or where it was if `edit`
+  /// returns `None`.
+  fn edit_selections(
+    &mut self,
+    mut edit: impl FnMut(&Buffer, Selection) -> Option<(Range<usize>, String)>,
+  ) {
+    let (mut selections, main) =
+      Selection::merge(&self.buffer().selections(), 0);
+
+    let (mut inserted, mut removed) = (0, 0);
+
+    for selection in &mut selections {
+      let shifted = Selection::new(
+        selection.anchor + inserted - removed,
+        selection.head + inserted - removed,
+      );
+
+      let Some((range, text)) = edit(self.buffer(), shifted) else {
+        *selection = shifted;
+        continue;
+      };
+
+      if !range.is_empty() {
+        self.remove(range.clone());
       }
-      WindowEvent::RedrawRequested => {
-        match self.render() {
-          Ok(_) => {}
-          Err(e) => {
-            self.error = Some(e);
-            event_loop.exit();
-          }
-        }
 
-        if let Some(window) = &self.window {
-          window.request_redraw();
-        }
+      if !text.is_empty() {
+        self.insert(range.start, &text);
+      }
+
+      let len = text.chars().count();
+
+      inserted += len;
+      removed += range.len();
+
+      *selection = Selection::cursor(range.start + len);
+    }
+
+    self.buffer_mut().set_selections(&selections, main);
+  }
+
+  /// Remove the selected text at every cursor, or for cursors without a
+  /// selection, the text between the cursor and `target` of it.
+  fn delete(&mut self, target: impl Fn(&Buffer, usize) -> usize) {
+    self.edit_selections(|buffer, selection| {
+      if !selection.is_empty() {
+        return Some((selection.range(), String::new()));
+      }
+
+      let target = target(buffer, selection.head);
+
+      (target != selection.head).then(|| {
+        (
+          target.min(selection.head)..target.max(selection.head),
+          String::new(),
+        )
+      })
+    });
+  }
+
+  /// Insert `text` at every cursor in place of the selected text.
+  fn replace_selections(&mut self, text: &str) {
+    self.edit_selections(|_, selection| Some((selection.range(), text.into())));
+  }
+
+  /// Move every cursor to `target` of its selection, extending the selection
+  /// if `select`, or else clearing it.
+  fn move_cursors(
+    &mut self,
+    select: bool,
+    target: impl Fn(&Buffer, Selection) -> usize,
+  ) {
+    let buffer = self.buffer();
+
+    let selections = buffer
+      .selections()
+      .into_iter()
+      .map(|selection| {
+        let head = target(buffer, selection);
+
+        Selection::new(if select { selection.anchor } else { head }, head)
+      })
+      .collect::<Vec<Selection>>();
+
+    self.buffer_mut().set_selections(&selections, 0);
+  }
+
+  /// Move every cursor `lines` lines down, or up if negative, to the column
+  /// it was at before passing through shorter lines. Moving past the first
+  /// or last line goes to the start or end of the buffer.
+  fn move_lines(&mut self, select: bool, lines: isize) {
+    let buffer = self.buffer();
+
+    let content = &buffer.content;
+
+    let selections = buffer
+      .selections()
+      .into_iter()
+      .map(|selection| {
+        let column = selection
+          .goal
+          .unwrap_or_else(|| buffer.column(selection.head));
+
+        let head = match content
+          .char_to_line(selection.head)
+          .checked_add_signed(lines)
+        {
+          Some(line) if line < content.len_lines() => {
+            buffer.column_position(line, column)
+          }
+          _ if lines < 0 => 0,
+          _ => content.len_chars(),
+        };
+
+        Selection {
+          goal: Some(column),
+          ..Selection::new(if select { selection.anchor } else { head }, head)
+        }
+      })
+      .collect::<Vec<Selection>>();
+
+    self.buffer_mut().set_selections(&selections, 0);
+  }
+
+  /// Add a cursor as the main one, at the same column as the topmost cursor
+  /// on the line above it, or as the bottommost on the line below.
+  fn add_cursor_vertically(&mut self, down: bool) {
+    let buffer = self.buffer();
+
+    let content = &buffer.content;
+
+    let mut selections = buffer.selections();
+
+    let from = selections.iter().copied();
+
+    let Some(from) = (if down {
+      from.max_by_key(|selection| selection.head)
+    } else {
+      from.min_by_key(|selection| selection.head)
+    }) else {
+      return;
+    };
+
+    let line = content.char_to_line(from.head);
+
+    let target = if down {
+      (line + 1 < content.len_lines()).then_some(line + 1)
+    } else {
+      line.checked_sub(1)
+    };
+
+    let Some(target) = target else {
+      return;
+    };
+
+    let column = from.goal.unwrap_or_else(|| buffer.column(from.head));
+
+    selections.push(Selection {
+      goal: Some(column),
+      ..Selection::cursor(buffer.column_position(target, column))
+    });
+
+    let main = selections.len() - 1;
+
+    self.buffer_mut().set_selections(&selections, main);
+  }
+
+  /// Add a cursor as the main one at `char_idx`.
+  fn add_cursor(&mut self, char_idx: usize) {
+    let mut selections = self.buffer().selections();
+
+    selections.push(Selection::cursor(char_idx));
+
+    let main = selections.len() - 1;
+
+    self.buffer_mut().set_selections(&selections, main);
+  }
+
+  /// Select the word at the main cursor if nothing is selected. Returns the
+  /// selected text, or `None` if there is no word there.
+  fn select_word(&mut self) -> Option<String> {
+    let buffer = self.buffer();
+
+    let range = match buffer.selection() {
+      Some(range) => range,
+      None => buffer.word_at(buffer.cursor, &self.word_separators)?,
+    };
+
+    let text = buffer.content.slice(range.clone()).to_string();
+
+    let buffer = self.buffer_mut();
+
+    buffer.anchor = Some(range.start);
+    buffer.cursor = range.end;
+    buffer.goal = None;
+
+    Some(text)
+  }
+
+  /// Select the word at the main cursor, or if text is already selected, add
+  /// a cursor selecting its next occurrence that is not yet selected.
+  fn select_next_occurrence(&mut self) {
+    if self.buffer().selection().is_none() {
+      self.select_word();
+      return;
+    }
+
+    let Some(text) = self.select_word() else {
+      return;
+    };
+
+    let buffer = self.buffer();
+
+    let mut selections = buffer.selections();
+
+    let occurrences = buffer.find_all(&text);
+
+    let after =
+      occurrences.partition_point(|range| range.start < buffer.cursor);
+
+    let next = occurrences[after..]
+      .iter()
+      .chain(&occurrences[..after])
+      .find(|range| {
+        !selections
+          .iter()
+          .any(|selection| selection.range() == **range)
+      });
+
+    let Some(next) = next else {
+      self.status = Some("no more occurrences".into());
+      return;
+    };
+
+    selections.push(Selection::new(next.start, next.end));
+
+    let main = selections.len() - 1;
+
+    self.buffer_mut().set_selections(&selections, main);
+  }
+
+  /// Select every occurrence of the selected text, or of the word at the
+  /// main cursor.
+  fn select_all_occurrences(&mut self) {
+    let Some(text) = self.select_word() else {
+      return;
+    };
+
+    let buffer = self.buffer();
+
+    let occurrences = buffer
+      .find_all(&text)
+      .into_iter()
+      .map(|range| Selection::new(range.start, range.end))
+      .collect::<Vec<Selection>>();
+
+    let main = occurrences
+      .iter()
+      .position(|selection| selection.head == buffer.cursor)
+      .unwrap_or_default();
+
+    if !occurrences.is_empty() {
+      self.buffer_mut().set_selections(&occurrences, main);
+    }
+  }
+
+  /// The text selected at every cursor, from first to last, on separate
+  /// lines, or `None` if nothing is selected.
+  fn selected_text(&self) -> Option<String> {
+    let buffer = self.buffer();
+
+    let (selections, _) = Selection::merge(&buffer.selections(), 0);
+
+    let texts = selections
+      .iter()
+      .filter(|selection| !selection.is_empty())
+      .map(|selection| buffer.content.slice(selection.range()).to_string())
+      .collect::<Vec<String>>();
+
+    (!texts.is_empty()).then(|| texts.join(buffer.line_ending.as_str()))
+  }
+
+  /// Copy the selected text to the clipboard. Returns whether it was copied.
+  fn copy(&mut self) -> bool {
+    let Some(text) = self.selected_text() else {
+      self.status = Some("nothing selected".into());
+      return false;
+    };
+
+    if let Err(error) = self.clipboard.set(text) {
+      self.status = Some(error.summary());
+      return false;
+    }
+
+    true
+  }
+
+  fn cut(&mut self) {
+    if self.copy() {
+      self.delete(|_, cursor| cursor);
+    }
+  }
+
+  fn paste(&mut self) {
+    match self.clipboard.get() {
+      Ok(Some(text)) => self.insert_pasted(&text),
+      Ok(None) => {}
+      Err(error) => self.status = Some(error.summary()),
+    }
+  }
+
+  /// Insert `text` at every cursor in place of the selected text, with its
+  /// line endings converted to the buffer's. If it has as many lines as there
+  /// are cursors, each cursor gets one line.
+  fn insert_pasted(&mut self, text: &str) {
+    let line_ending = self.buffer().line_ending;
+
+    let (text, _) = line_ending.convert(&Rope::from_str(text), 0);
+
+    let text = text.to_string();
+
+    let separator = line_ending.as_str();
+
+    let mut lines = text
+      .strip_suffix(separator)
+      .unwrap_or(&text)
+      .split(separator);
+
+    let cursors = self.buffer().cursors.len() + 1;
+
+    let distribute = cursors > 1 && lines.clone().count() == cursors;
+
+    self.edit_selections(|_, selection| {
+      let text = if distribute {
+        lines.next().unwrap_or_default()
+      } else {
+        &text
+      };
+
+      Some((selection.range(), text.into()))
+    });
+  }
+
+  /// Put the main selection in the primary selection.
+  fn update_primary(&mut self) {
+    let buffer = self.buffer();
+
+    let Some(range) = buffer.selection() else {
+      return;
+    };
+
+    let text = buffer.content.slice(range).to_string();
+
+    if let Err(error) = self.clipboard.set_primary(text) {
+      self.status = Some(error.summary());
+    }
+  }
+
+  fn select_all(&mut self) {
+    let len = self.buffer().content.len_chars();
+
+    self
+      .buffer_mut()
+      .set_selections(&[Selection::new(0, len)], 0);
+  }
+
+  /// Move the active buffer to another state in its history, or set
+  /// `status` if `travel` finds none.
+  fn travel(
+    &mut self,
+    travel: impl FnOnce(&mut History) -> Option<Travel>,
+    status: &str,
+  ) -> bool {
+    let Some(edits) = self.buffer_mut().travel(travel) else {
+      self.status = Some(status.into());
+      return false;
+    };
+
+    for edit in edits {
+      self.shift_cursors(
+        edit.position,
+        edit.removed.chars().count(),
+        edit.inserted.chars().count(),
+      );
+    }
+
+    true
+  }
+
+  fn undo(&mut self) {
+    self.travel(History::undo, "nothing to undo");
+  }
+
+  fn redo(&mut self) {
+    self.travel(History::redo, "nothing to redo");
+  }
+
+  /// Move to the state created before the current one, even on another
+  /// branch of the undo tree.
+  fn earlier(&mut self) {
+    self.travel(History::earlier, "already at the oldest change");
+  }
+
+  fn later(&mut self) {
+    self.travel(History::later, "already at the newest change");
+  }
+
+  /// Move to a sibling branch of the undo tree, `offset` places along.
+  fn switch_branch(&mut self, offset: isize) {
+    let mut position = None;
+
+    let moved = self.travel(
+      |history| {
+        let (travel, index, count) = history.switch_branch(offset)?;
+        position = Some((index, count));
+        Some(travel)
+      },
+      "no other branches",
+    );
+
+    if moved && let Some((index, count)) = position {
+      self.status = Some(format!("branch {index} of {count}"));
+    }
+  }
+
+  /// Go back to the state of the buffer at the time given by `input`, such
+  /// as `10m` for ten minutes ago.
+  fn go_back(&mut self, input: &str) {
+    let Some(ago) = parse_duration(input) else {
+      self.status = Some(format!("invalid duration `{}`", input.trim()));
+      return;
+    };
+
+    self.travel(|history| history.go_back(ago), "no earlier state");
+  }
+
+  /// Unfocused panes showing the active buffer.
+  fn other_panes_mut(&mut self) -> impl Iterator<Item = &mut Pane> {
+    let (focus, buffer) = (self.focus, self.buffer().id);
+
+    self
+      .panes
+      .iter_mut()
+      .filter(move |(id, pane)| **id != focus && pane.buffer == buffer)
+      .map(|(_, pane)| pane)
+  }
+
+  pub fn set_scroll_margin(&mut self, margin: usize) {
+    self.scroll_margin = margin;
+  }
+
+  pub fn set_word_separators(&mut self, separators: String) {
+    self.word_separators = separators;
+  }
+
+  pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+    self.clipboard = Box::new(clipboard);
+  }
+
+  /// Start writing swap files for this session, and offer to restore any
+  /// buffers recovered from earlier sessions.
+  pub fn set_swap(&mut self, swap: Swap) {
+    self.recoveries = swap.recoverable();
+    self.swap = Some(swap);
+    self.prompt_recovery();
+  }
+
+  /// Set the proxy used to send events to the event loop from background
+  /// threads.
+  pub fn set_proxy(&mut self, proxy: EventLoopProxy<UserEvent>) {
+    self.proxy = Some(proxy);
+  }
+
+  /// Watch the files behind the buffers for changes made by other programs.
+  pub fn set_watcher(&mut self, watcher: Watcher) {
+    for path in self
+      .buffers
+      .iter()
+      .filter_map(|buffer| buffer.path.as_ref())
+    {
+      watcher.watch(path);
+    }
+
+    self.watcher = Some(watcher);
+  }
+
+  fn set_path(&mut self, path: Option<PathBuf>) {
+    let active = self.active();
+
+    let buffer = &mut self.buffers[active];
+
+    if path == buffer.path {
+      return;
+    }
+
+    if let Some(watcher) = &self.watcher {
+      if let Some(old) = &buffer.path {
+        watcher.unwatch(old);
+      }
+
+      if let Some(new) = &path {
+        watcher.watch(new);
+      }
+    }
+
+    buffer.path = path;
+    self.update_title();
+  }
+
+  pub fn handle_user_event(&mut self, event: UserEvent) {
+    match event {
+      UserEvent::FileChanged(path) => {
+        for buffer in &mut self.buffers {
+          if buffer.path.as_ref() == Some(&path) {
+            buffer.file_changed = true;
+          }
+        }
+
+        self.check_file_changed();
+      }
+      UserEvent::FinderDone { generation } => {
+        if let Some(finder) = &mut self.finder {
+          finder.finish(generation);
+        }
+      }
+      UserEvent::FinderPaths { generation, paths } => {
+        if let Some(finder) = &mut self.finder {
+          finder.add(generation, paths);
+        }
+      }
+    }
+  }
+
+  fn prompt_recovery(&mut self) {
+    if let Some(recovery) = self.recoveries.last() {
+      self.prompt = Some(Prompt::new(
+        PromptKind::Recover(recovery.snapshot.path.clone()),
+        "",
+      ));
+    }
+  }
+
+  fn recover(&mut self, choice: &str) {
+    let Some(recovery) = self.recoveries.pop() else {
+      return;
+    };
+
+    match choice {
+      "r" => {
+        let Snapshot { content, path } = recovery.snapshot;
+
+        let encoding = path
+          .as_ref()
+          .and_then(|path| fs::read(path).ok())
+          .map(|bytes| Encoding::detect(&bytes))
+          .unwrap_or_default();
+
+        let id = self.new_buffer_id();
+
+        let mut buffer = Buffer {
+          disk_stamp: path.as_deref().and_then(Stamp::of),
+          path,
+          revision: 1,
+          ..Buffer::with_content(id, content, encoding)
+        };
+
+        // Undoing never returns a recovered buffer to what is on disk.
+        buffer.history.clear_saved();
+
+        // A buffer already open on the same file is replaced, keeping its
+        // swap file, which now holds the only copy of the recovered changes.
+        match self
+          .buffers
+          .iter()
+          .position(|open| open.path.is_some() && open.path == buffer.path)
+        {
+          Some(index) => {
+            let open = &mut self.buffers[index];
+
+            *open = Buffer {
+              id: open.id,
+              revision: open.revision + 1,
+              swap_revision: open.swap_revision,
+              ..buffer
+            };
+
+            self.activate(index);
+          }
+          None => self.add_buffer(buffer),
+        }
+      }
+      "d" => {}
+      _ => {
+        self.prompt_recovery();
+        return;
+      }
+    }
+
+    if let Err(error) =
+      fs::remove_file(&recovery.path).context(error::RemoveFile {
+        path: &recovery.path,
+      })
+    {
+      self.status = Some(error.summary());
+    }
+
+    self.prompt_recovery();
+  }
+
+  fn update_swap(&mut self) {
+    let Some(swap) = &self.swap else {
+      return;
+    };
+
+    for buffer in &mut self.buffers {
+      let path = swap.path(buffer.id);
+
+      if buffer.is_modified() {
+        if buffer.swap_revision != Some(buffer.revision) {
+          swap.update(&path, buffer.snapshot());
+          buffer.swap_revision = Some(buffer.revision);
+        }
+      } else if buffer.swap_revision.take().is_some() {
+        swap.remove(&path);
+      }
+    }
+  }
+
+  fn title(&self) -> String {
+    let buffer = self.buffer();
+
+    let marker = if buffer.is_dirty() { "*" } else { "" };
+
+    format!("{marker}{} - {}", buffer.name(), env!("CARGO_PKG_NAME"))
+  }
+
+  fn set_encoding(&mut self, name: &str) {
+    let encoding = match name.parse::<Encoding>() {
+      Ok(encoding) => encoding,
+      Err(error) => {
+        self.status = Some(error.summary());
+        return;
+      }
+    };
+
+    let buffer = self.buffer_mut();
+
+    if encoding != buffer.encoding {
+      buffer.encoding = encoding;
+      buffer.revision += 1;
+      buffer.history.clear_saved();
+    }
+
+    self.status =
+      Some(match encoding.encode(&self.buffer().content.to_string()) {
+        Ok(_) => format!("Encoding set to {encoding}"),
+        Err(error) => format!("warning: {}", error.summary()),
+      });
+  }
+
+  fn set_line_ending(&mut self, line_ending: LineEnding) {
+    self.buffer_mut().set_line_ending(line_ending);
+    self.status = Some(format!("Converted line endings to {line_ending}"));
+  }
+
+  /// Finish editing the piped buffer, and write it to stdout on exit.
+  fn commit(&mut self) {
+    let Some(buffer) = self.buffers.iter().find(|buffer| buffer.stdin) else {
+      return;
+    };
+
+    match buffer.encoding.encode(&buffer.content.to_string()) {
+      Ok(output) => {
+        self.pipe = Some(Pipe::Committed(output));
+        self.quit();
+      }
+      Err(error) => self.status = Some(error.summary()),
+    }
+  }
+
+  fn close_requested(&mut self) {
+    if self.pipe.is_some() {
+      self.commit();
+    } else {
+      self.quit();
+    }
+  }
+
+  /// Exit, once the user has decided what to do with each modified buffer.
+  fn quit(&mut self) {
+    if let Err(error) = self.save_scratch() {
+      self.status = Some(error.summary());
+    }
+
+    if let Some(index) = self
+      .buffers
+      .iter()
+      .position(|buffer| !buffer.stdin && buffer.is_modified())
+    {
+      self.activate(index);
+      self.after_save = Some(AfterSave::Quit);
+      self.prompt = Some(Prompt::new(PromptKind::UnsavedChanges, ""));
+      return;
+    }
+
+    if self.pipe == Some(Pipe::Open) {
+      self.pipe = Some(Pipe::Cancelled);
+    }
+
+    self.exit = true;
+  }
+
+  /// Forget about closing the buffer or quitting once it has been saved, as
+  /// when the user backs out of a prompt or saving fails.
+  fn cancel_pending(&mut self) {
+    self.after_save = None;
+
+    if let Some(Pipe::Committed(_)) = self.pipe {
+      self.pipe = Some(Pipe::Open);
+    }
+  }
+
+  fn save_scratch(&mut self) -> Result {
+    self.scratch_saved_at = Instant::now();
+
+    for buffer in &mut self.buffers {
+      buffer.save_scratch()?;
+    }
+
+    Ok(())
+  }
+
+  fn autosave_scratch(&mut self) {
+    if self.buffers.iter().any(Buffer::needs_scratch_save)
+      && self.scratch_saved_at.elapsed() >= AUTOSAVE_INTERVAL
+      && let Err(error) = self.save_scratch()
+    {
+      self.status = Some(error.summary());
+    }
+  }
+
+  /// Window area of the focused pane's cursor, or of the text being composed
+  /// if the cursor is hidden within it, for the input method to show its
+  /// candidates next to.
+  fn cursor_area(
+    &self,
+    view: &View,
+  ) -> Option<(PhysicalPosition<f32>, PhysicalSize<f32>)> {
+    let pane = view.panes.iter().find(|pane| pane.focused)?;
+
+    let (row, column) = pane.cursor.or_else(|| {
+      pane
+        .preedit
+        .as_ref()
+        .map(|(row, columns)| (*row, columns.start))
+    })?;
+
+    let before = pane
+      .lines
+      .get(row)
+      .map(|line| line.chars().take(column).collect::<String>())
+      .unwrap_or_default();
+
+    Some((
+      PhysicalPosition::new(
+        pane.rect.x + Pane::PADDING - pane.scroll_offset.0
+          + self.text_width(&before),
+        pane.rect.y + Pane::PADDING - pane.scroll_offset.1
+          + row as f32 * self.metrics.line_height,
+      ),
+      PhysicalSize::new(self.metrics.advance, self.metrics.line_height),
+    ))
+  }
+
+  /// Tell the input method where the cursor is, if it has moved since last
+  /// time.
+  fn update_ime_cursor_area(&mut self, view: &View) {
+    let area = self.cursor_area(view);
+
+    if area == self.ime_cursor_area {
+      return;
+    }
+
+    self.ime_cursor_area = area;
+
+    if let (Some(window), Some((position, size))) = (&self.window, area) {
+      window.set_ime_cursor_area(position, size);
+    }
+  }
+
+  fn update_title(&self) {
+    if let Some(window) = &self.window {
+      window.set_title(&self.title());
+    }
+  }
+
+  fn save(&mut self) {
+    match self.buffer().path.clone() {
+      Some(path) => self.write(path),
+      None => self.save_as(),
+    }
+  }
+
+  fn save_as(&mut self) {
+    let input = self
+      .buffer()
+      .path
+      .as_ref()
+      .map(|path| path.display().to_string())
+      .unwrap_or_default();
+
+    self.prompt = Some(Prompt::new(PromptKind::SaveAs, input));
+  }
+
+  fn write(&mut self, path: PathBuf) {
+    let buffer = self.buffer();
+
+    match buffer
+      .encoding
+      .encode(&buffer.content.to_string())
+      .and_then(|bytes| atomic_write(&path, &bytes))
+    {
+      Ok(()) => {
+        self.status = Some(format!("Saved {}", path.display()));
+        self.buffer_mut().disk_stamp = Stamp::of(&path);
+        self.set_path(Some(path));
+
+        let buffer = self.buffer_mut();
+
+        // The buffer now lives in its own file, so start the next session
+        // with an empty scratch buffer.
+        if let Some(scratch) = buffer.scratch.take()
+          && let Err(error) = scratch.save(&Rope::new(), 0)
+        {
+          log::warn!("scratch: {}", error.summary());
+        }
+
+        buffer.mark_saved();
+
+        if let Err(error) = buffer.save_history() {
+          log::warn!("undo history: {}", error.summary());
+        }
+
+        self.update_title();
+
+        match self.after_save.take() {
+          Some(AfterSave::Close) => self.remove_buffer(),
+          Some(AfterSave::Quit) => self.quit(),
+          None => {}
+        }
+      }
+      Err(error) => {
+        self.cancel_pending();
+        self.status = Some(error.summary());
+      }
+    }
+  }
+
+  fn indicators(&self) -> String {
+    let buffer = self.buffer();
+
+    format!("{}  {}", buffer.encoding, buffer.line_ending)
+  }
+
+  fn tabs(&self) -> Vec<Tab> {
+    let active = self.active();
+
+    self
+      .buffers
+      .iter()
+      .enumerate()
+      .map(|(index, buffer)| Tab {
+        active: index == active,
+        modified: buffer.is_dirty(),
+        name: buffer.name(),
+      })
+      .collect()
+  }
+
+  fn view(&self) -> View {
+    let panes = self
+      .layout
+      .layout(self.text_area())
+      .into_iter()
+      .map(|(id, rect)| {
+        let focused = id == self.focus;
+
+        let rows = self.rows(rect);
+
+        if focused && let Some(diff) = &self.diff {
+          return PaneView {
+            cursor: None,
+            focused,
+            lines: diff
+              .lines()
+              .skip(self.diff_scroll)
+              .take(rows)
+              .map(str::to_owned)
+              .collect(),
+            other_cursors: Vec::new(),
+            preedit: None,
+            rect,
+            scroll_offset: (0.0, 0.0),
+            selection: Vec::new(),
+          };
+        }
+
+        let pane = &self.panes[&id];
+
+        let Some(buffer) = self.buffers.iter().find(|b| b.id == pane.buffer)
+        else {
+          return PaneView {
+            cursor: None,
+            focused,
+            lines: Vec::new(),
+            other_cursors: Vec::new(),
+            preedit: None,
+            rect,
+            scroll_offset: (0.0, 0.0),
+            selection: Vec::new(),
+          };
+        };
+
+        let content = &buffer.content;
+
+        let len = content.len_chars();
+
+        let selections = if focused {
+          buffer.selections()
+        } else {
+          vec![Selection::new(
+            pane.anchor.unwrap_or(pane.cursor),
+            pane.cursor,
+          )]
+        }
+        .into_iter()
+        .map(|selection| {
+          Selection::new(selection.anchor.min(len), selection.head.min(len))
+        })
+        .collect::<Vec<Selection>>();
+
+        let scroll = pane.scroll.min(content.len_lines() - 1);
+
+        let scroll_column = pane.scroll_column;
+
+        // A line scrolled partway out of view at the top lets part of
+        // another in at the bottom.
+        let rows = rows + usize::from(pane.scroll_offset.1 > 0.0);
+
+        // Row and column of `char_idx`, if it is visible.
+        let position = |char_idx: usize| {
+          let line = content.char_to_line(char_idx);
+
+          let column = char_idx - content.line_to_char(line);
+
+          ((scroll..scroll + rows).contains(&line) && column >= scroll_column)
+            .then(|| (line - scroll, column - scroll_column))
+        };
+
+        let mut selection = selections
+          .iter()
+          .filter(|selection| !selection.is_empty())
+          .flat_map(|selection| {
+            let first = content.char_to_line(selection.start()).max(scroll);
+
+            let last =
+              content.char_to_line(selection.end()).min(scroll + rows - 1);
+
+            (first..=last).filter_map(move |line| {
+              let start = content.line_to_char(line);
+
+              // Past the end of the line only if the selection includes its
+              // line ending.
+              let columns = selection
+                .start()
+                .saturating_sub(start)
+                .saturating_sub(scroll_column)
+                ..(selection.end() - start)
+                  .min(buffer.line_len(line) + 1)
+                  .saturating_sub(scroll_column);
+
+              (!columns.is_empty()).then_some((line - scroll, columns))
+            })
+          })
+          .collect::<Vec<(usize, Range<usize>)>>();
+
+        let mut cursor = position(selections[0].head);
+
+        let mut lines = content
+          .lines_at(scroll)
+          .take(rows)
+          .map(|line| {
+            line
+              .chars()
+              .skip(scroll_column)
+              .collect::<String>()
+              .trim_end_matches(['\n', '\r'])
+              .to_owned()
+          })
+          .collect::<Vec<String>>();
+
+        let mut other_cursors = selections[1..]
+          .iter()
+          .filter_map(|selection| position(selection.head))
+          .collect::<Vec<(usize, usize)>>();
+
+        let mut preedit = None;
+
+        // Show the text being composed at the cursor, pushing what follows
+        // it on the line along.
+        if focused
+          && let Some((text, text_cursor)) = &self.preedit
+          && let Some((row, column)) = cursor
+        {
+          let len = text.chars().count();
+
+          let line = &mut lines[row];
+
+          line.insert_str(
+            line
+              .char_indices()
+              .nth(column)
+              .map_or(line.len(), |(i, _)| i),
+            text,
+          );
+
+          let shift = |(other_row, other_column): (usize, usize)| {
+            if other_row == row && other_column >= column {
+              (other_row, other_column + len)
+            } else {
+              (other_row, other_column)
+            }
+          };
+
+          other_cursors = other_cursors.into_iter().map(shift).collect();
+
+          for (other_row, columns) in &mut selection {
+            *columns = shift((*other_row, columns.start)).1
+              ..shift((*other_row, columns.end)).1;
+          }
+
+          cursor = text_cursor
+            .map(|(start, _)| (row, column + text[..start].chars().count()));
+
+          preedit = Some((row, column..column + len));
+        }
+
+        PaneView {
+          cursor,
+          focused,
+          lines,
+          other_cursors,
+          preedit,
+          rect,
+          scroll_offset: pane.scroll_offset,
+          selection,
+        }
+      })
+      .collect();
+
+    View {
+      finder: self
+        .finder
+        .as_ref()
+        .map(|finder| finder.view(FinderView::ROWS)),
+      indicators: self.indicators(),
+      panes,
+      status_line: self.status_line(),
+      tabs: self.tabs(),
+    }
+  }
+
+  fn status_line(&self) -> Option<String> {
+    match &self.prompt {
+      Some(prompt) => Some(prompt.message()),
+      None => self.status.clone(),
+    }
+  }
+
+  fn resize(&mut self, new_size: PhysicalSize<u32>) {
+    if new_size.width > 0 && new_size.height > 0 {
+      self.size = new_size;
+
+      if let Some(renderer) = &mut self.renderer {
+        renderer.resize(new_size);
+      }
+
+      self.scroll_to_cursor();
+    }
+  }
+
+  fn render(&mut self) -> Result {
+    let view = self.view();
+
+    self.update_ime_cursor_area(&view);
+
+    if let Some(renderer) = &mut self.renderer {
+      renderer.render(&view)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl ApplicationHandler<UserEvent> for App {
+  fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+    if self.window.is_none() {
+      let window = match event_loop
+        .create_window(
+          WindowAttributes::default()
+            .with_inner_size(PhysicalSize {
+              width: 1600,
+              height: 1200,
+            })
+            .with_min_inner_size(PhysicalSize {
+              width: 800,
+              height: 600,
+            })
+            .with_title(self.title()),
+        )
+        .context(error::CreateWindow)
+      {
+        Ok(window) => Arc::new(window),
+        Err(err) => {
+          self.error = Some(err);
+          event_loop.exit();
+          return;
+        }
+      };
+
+      let window_clone = window.clone();
+
+      let future = async move { Renderer::new(window_clone).await };
+
+      match pollster::block_on(future) {
+        Ok(renderer) => {
+          self.metrics = renderer.metrics();
+          self.size = window.inner_size();
+          self.renderer = Some(renderer);
+          window.set_ime_allowed(true);
+          self.window = Some(window);
+        }
+        Err(err) => {
+          self.error = Some(err);
+          event_loop.exit();
+          return;
+        }
+      };
+
+      if let Some(window) = &self.window {
+        window.request_redraw();
+      }
+    }
+  }
+
+  fn window_event(
+    &mut self,
+    event_loop: &ActiveEventLoop,
+    _id: WindowId,
+    event: WindowEvent,
+  ) {
+    match event {
+      WindowEvent::CloseRequested => {
+        self.close_requested();
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.mouse_position = None;
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        self.handle_cursor_moved(position);
+      }
+      WindowEvent::ModifiersChanged(modifiers) => {
+        self.modifiers = modifiers.state();
+      }
+      WindowEvent::MouseInput { state, button, .. } => {
+        self.handle_mouse_input(state, button);
+      }
+      WindowEvent::MouseWheel { delta, phase, .. } => {
+        self.handle_mouse_wheel(delta, phase);
+      }
+      WindowEvent::Ime(ime) => {
+        let title = self.title();
+
+        self.handle_ime(ime);
+
+        if title != self.title() {
+          self.update_title();
+        }
+      }
+      WindowEvent::Resized(new_size) => {
+        self.resize(new_size);
+      }
+      WindowEvent::KeyboardInput { event, .. } => {
+        let title = self.title();
+
+        let unmodified = event.key_without_modifiers();
+
+        self.handle_key_event(event.logical_key, &unmodified, event.state);
+
+        if title != self.title() {
+          self.update_title();
+        }
+
+        if let Some(window) = &self.window {
+          window.request_redraw();
+        }
+      }
+      WindowEvent::RedrawRequested => {
+        match self.render() {
+          Ok(_) => {}
+          Err(e) => {
+            self.error = Some(e);
+            event_loop.exit();
+          }
+        }
+
+        if let Some(window) = &self.window {
+          window.request_redraw();
+        }
       }
       _ => {}
     }
+
+    // Hand edits to the swap straight away rather than once the event loop
+    // is idle, so that the panic hook can write them if a later event in the
+    // same batch panics.
+    self.update_swap();
+
+    if self.exit {
+      event_loop.exit();
+    }
+  }
+
+  fn user_event(&mut self, _: &ActiveEventLoop, event: UserEvent) {
+    self.handle_user_event(event);
+
+    self.update_title();
+
+    if let Some(window) = &self.window {
+      window.request_redraw();
+    }
+  }
+
+  fn exiting(&mut self, _: &ActiveEventLoop) {
+    if let Err(error) = self.save_scratch() {
+      log::warn!("scratch: {}", error.summary());
+    }
+
+    if self.error.is_some() {
+      self.update_swap();
+    } else if let Some(swap) = &self.swap {
+      for buffer in &mut self.buffers {
+        if buffer.swap_revision.take().is_some() {
+          swap.remove(&swap.path(buffer.id));
+        }
+      }
+    }
+  }
+
+  fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+    self.animate_scroll();
+    self.auto_scroll();
+    self.autosave_scratch();
+    self.update_swap();
+
+    if let Some(finder) = &mut self.finder {
+      finder.score();
+    }
+
+    if let Some(window) = &self.window {
+      window.request_redraw();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*, proptest::prelude::*, tempfile::TempDir,
+    unicode_segmentation::UnicodeSegmentation,
+  };
+
+  #[test]
+  fn insert_character() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+    assert_eq!(app.buffer().cursor, 1);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+    assert_eq!(app.buffer().cursor, 2);
+  }
+
+  #[test]
+  fn backspace() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+    assert_eq!(app.buffer().cursor, 1);
+  }
+
+  #[test]
+  fn delete_character() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Delete),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+    assert_eq!(app.buffer().cursor, 1);
+  }
+
+  #[test]
+  fn cursor_movement() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 1);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowRight),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 2);
+  }
+
+  #[test]
+  fn home_end_keys() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Named(NamedKey::Home), ElementState::Pressed);
+
+    assert_eq!(app.buffer().cursor, 0);
+
+    app.handle_keyboard_input(Key::Named(NamedKey::End), ElementState::Pressed);
+
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn enter_key() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "a\nb");
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn space_key() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Space),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "a b");
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn insert_at_cursor_position() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "abc");
+    assert_eq!(app.buffer().cursor, 2);
+  }
+
+  #[test]
+  fn multiple_characters_deletion() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("e".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("f".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "abc");
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn boundary_conditions() {
+    let mut app = App::new();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert_eq!(app.buffer().cursor, 0);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Delete),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert_eq!(app.buffer().cursor, 0);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 0);
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowRight),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 1);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowRight),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 1);
+  }
+
+  #[test]
+  fn insert_multi_char_string() {
+    let mut app = App::new();
+
+    app.handle_keyboard_input(
+      Key::Character("hello".into()),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "hello");
+    assert_eq!(app.buffer().cursor, 5);
+  }
+
+  #[test]
+  fn open_existing_file() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, "hello\nworld").unwrap();
+
+    let app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "hello\nworld");
+    assert_eq!(app.buffer().cursor, 0);
+    assert_eq!(app.buffer().path, Some(path));
+    assert_eq!(app.title(), "foo.txt - scratchpad");
+  }
+
+  #[test]
+  fn open_nonexistent_file() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert_eq!(app.buffer().path, Some(path.clone()));
+    assert!(!path.exists());
+  }
+
+  #[test]
+  fn open_directory() {
+    let tempdir = TempDir::new().unwrap();
+
+    assert!(App::open(tempdir.path().into()).is_err());
+  }
+
+  #[test]
+  fn save() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, "foo").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "afoo");
+    assert_eq!(app.buffer().content.to_string(), "afoo");
+    assert!(app.prompt.is_none());
+  }
+
+  #[test]
+  fn save_without_path_prompts() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::SaveAs);
+
+    app.modifiers = ModifiersState::empty();
+
+    app.handle_keyboard_input(
+      Key::Character(path.to_str().unwrap().into()),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffer().path, Some(path.clone()));
+    assert_eq!(app.buffer().content.to_string(), "a");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+  }
+
+  #[test]
+  fn save_as() {
+    let tempdir = TempDir::new().unwrap();
+
+    let original = tempdir.path().join("foo.txt");
+
+    let copy = tempdir.path().join("bar.txt");
+
+    std::fs::write(&original, "foo").unwrap();
+
+    let mut app = App::open(original.clone()).unwrap();
+
+    app.modifiers = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+    app
+      .handle_keyboard_input(Key::Character("S".into()), ElementState::Pressed);
+
+    assert_eq!(
+      app.prompt.as_ref().unwrap().input,
+      original.display().to_string()
+    );
+
+    app.prompt.as_mut().unwrap().input = copy.display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().path, Some(copy.clone()));
+    assert_eq!(app.title(), "bar.txt - scratchpad");
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), "foo");
+  }
+
+  #[test]
+  fn save_as_cancel() {
+    let mut app = App::new();
+
+    app.modifiers = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+    app
+      .handle_keyboard_input(Key::Character("S".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffer().path, None);
+  }
+
+  #[test]
+  fn save_failure_is_reported() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("missing/foo.txt");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert!(
+      app
+        .status
+        .as_ref()
+        .unwrap()
+        .starts_with("failed to create temporary file for")
+    );
+
+    assert!(app.error.is_none());
+    assert!(!path.exists());
+  }
+
+  #[test]
+  fn modified_flag() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::open(path).unwrap();
+
+    assert!(!app.buffer().is_modified());
+    assert_eq!(app.title(), "foo.txt - scratchpad");
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    assert!(app.buffer().is_modified());
+    assert_eq!(app.title(), "*foo.txt - scratchpad");
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert!(!app.buffer().is_modified());
+    assert_eq!(app.title(), "foo.txt - scratchpad");
+  }
+
+  #[test]
+  fn cursor_movement_does_not_modify() {
+    let mut app = App::new();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(Key::Named(NamedKey::End), ElementState::Pressed);
+
+    assert!(!app.buffer().is_modified());
+  }
+
+  #[test]
+  fn quit_without_changes() {
+    let mut app = App::new();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.exit);
+    assert!(app.prompt.is_none());
+  }
+
+  #[test]
+  fn quit_with_changes_prompts() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(!app.exit);
+
+    assert_eq!(
+      app.prompt.as_ref().unwrap().kind,
+      PromptKind::UnsavedChanges
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("x".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_some());
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    assert!(!app.exit);
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffer().content.to_string(), "a");
+  }
+
+  #[test]
+  fn quit_discard() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    assert!(app.exit);
+  }
+
+  #[test]
+  fn quit_save() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert!(app.exit);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+  }
+
+  #[test]
+  fn quit_save_without_path() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert!(!app.exit);
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::SaveAs);
+
+    app.prompt.as_mut().unwrap().input = path.display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.exit);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+  }
+
+  #[test]
+  fn quit_save_as_cancel() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(!app.exit);
+    assert_eq!(app.after_save, None);
+    assert!(app.prompt.is_none());
+  }
+
+  #[test]
+  fn recover_restore() {
+    let tempdir = TempDir::new().unwrap();
+
+    let recovered = tempdir.path().join("1-0.swp");
+
+    std::fs::write(&recovered, "/tmp/foo.txt\nhello").unwrap();
+
+    let mut app = App::new();
+
+    app.set_swap(Swap::new(tempdir.path().into()).unwrap());
+
+    assert_eq!(
+      app.prompt.as_ref().unwrap().kind,
+      PromptKind::Recover(Some("/tmp/foo.txt".into()))
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("r".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffer().content.to_string(), "hello");
+    assert_eq!(app.buffer().path, Some("/tmp/foo.txt".into()));
+    assert!(app.buffer().is_modified());
+    assert!(!recovered.exists());
+  }
+
+  #[test]
+  fn recover_discard_and_keep() {
+    let tempdir = TempDir::new().unwrap();
+
+    let first = tempdir.path().join("1-0.swp");
+    let second = tempdir.path().join("1-1.swp");
+
+    std::fs::write(&first, "\nfoo").unwrap();
+    std::fs::write(&second, "\nbar").unwrap();
+
+    let mut app = App::new();
+
+    app.set_swap(Swap::new(tempdir.path().into()).unwrap());
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.prompt.is_some());
+    assert!(second.exists());
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_none());
+    assert!(!first.exists());
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert!(!app.buffer().is_modified());
+  }
+
+  #[test]
+  fn swap_modified_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let mut app = App::new();
+
+    app.set_swap(Swap::new(tempdir.path().into()).unwrap());
+
+    let path = app.swap.as_ref().unwrap().path(0);
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.update_swap();
+
+    drop(app);
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "\na");
+  }
+
+  #[test]
+  fn scratch_is_restored() {
+    let tempdir = TempDir::new().unwrap();
+
+    let mut app = App::scratch(Scratch::new(tempdir.path().into())).unwrap();
+
+    assert_eq!(app.title(), "scratch - scratchpad");
+
+    app.handle_keyboard_input(
+      Key::Character("hello".into()),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.exit);
+    assert!(app.prompt.is_none());
+
+    let app = App::scratch(Scratch::new(tempdir.path().into())).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "hello");
+    assert_eq!(app.buffer().cursor, 4);
+    assert!(!app.buffer().is_modified());
+  }
+
+  #[test]
+  fn scratch_autosave() {
+    let tempdir = TempDir::new().unwrap();
+
+    let mut app = App::scratch(Scratch::new(tempdir.path().into())).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.scratch_saved_at = Instant::now() - AUTOSAVE_INTERVAL;
+
+    app.autosave_scratch();
+
+    assert!(!app.buffer().is_modified());
+
+    assert_eq!(
+      std::fs::read_to_string(tempdir.path().join("scratch.txt")).unwrap(),
+      "a"
+    );
+  }
+
+  #[test]
+  fn scratch_save_as() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app =
+      App::scratch(Scratch::new(tempdir.path().join("scratch"))).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    app.prompt.as_mut().unwrap().input = path.display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.buffer().scratch.is_none());
+    assert_eq!(app.title(), "foo.txt - scratchpad");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+
+    let app =
+      App::scratch(Scratch::new(tempdir.path().join("scratch"))).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "");
+  }
+
+  #[test]
+  fn open_detects_line_ending() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, "a\r\nb\r\n").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().line_ending, LineEnding::Crlf);
+    assert_eq!(app.indicators(), "UTF-8  CRLF");
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::End);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "a\r\nb\r\n\r\nc");
+    assert_eq!(app.buffer().cursor, 9);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\r\nb\r\n\r\nc");
+  }
+
+  #[test]
+  fn crlf_is_a_single_position() {
+    let mut app = App::new();
+
+    app.buffer_mut().line_ending = LineEnding::Crlf;
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 1);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowRight),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().cursor, 3);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Backspace),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+    assert_eq!(app.buffer().cursor, 1);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Delete),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+    assert_eq!(app.buffer().cursor, 1);
+  }
+
+  #[test]
+  fn convert_line_endings() {
+    let mut app = App::new();
+
+    app.buffer_mut().insert(0, "a\nb\r\nc");
+
+    app.buffer_mut().cursor = 6;
+
+    app.modifiers = ModifiersState::ALT;
+
+    app
+      .handle_keyboard_input(Key::Character("l".into()), ElementState::Pressed);
+
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::LineEnding);
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "a\r\nb\r\nc");
+    assert_eq!(app.buffer().cursor, 7);
+    assert_eq!(app.buffer().line_ending, LineEnding::Crlf);
+    assert_eq!(app.indicators(), "UTF-8  CRLF");
+  }
+
+  #[test]
+  fn open_and_save_preserve_encoding() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, b"\xFF\xFEh\0\xE9\0").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().encoding, Encoding::Utf16Le);
+    assert_eq!(app.buffer().content.to_string(), "h\u{e9}");
+    assert_eq!(app.status, None);
+
+    app.handle_keyboard_input(Key::Named(NamedKey::End), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("!".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(std::fs::read(&path).unwrap(), b"\xFF\xFEh\0\xE9\0!\0");
+  }
+
+  #[test]
+  fn open_lossy() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, b"\xFF\xFEa\0\x00\xD8").unwrap();
+
+    let app = App::open(path).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "a\u{fffd}");
+
+    assert!(
+      app
+        .status
+        .unwrap()
+        .contains("is not valid UTF-16LE, invalid bytes were replaced")
+    );
+  }
+
+  #[test]
+  fn open_damaged_utf8() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, b"caf\xC3\xA9 na\xC3\xAFve \xE9t\xC3\xA9").unwrap();
+
+    let app = App::open(path).unwrap();
+
+    assert_eq!(app.buffer().encoding, Encoding::Utf8);
+
+    assert_eq!(
+      app.buffer().content.to_string(),
+      "caf\u{e9} na\u{ef}ve \u{fffd}t\u{e9}"
+    );
+
+    assert!(
+      app
+        .status
+        .unwrap()
+        .contains("is not valid UTF-8, invalid bytes were replaced")
+    );
+  }
+
+  #[test]
+  fn change_encoding() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, "caf\u{e9}").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().encoding, Encoding::Utf8);
+
+    app.modifiers = ModifiersState::ALT;
+
+    app
+      .handle_keyboard_input(Key::Character("e".into()), ElementState::Pressed);
+
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::Encoding);
+
+    app.modifiers = ModifiersState::empty();
+
+    app.handle_keyboard_input(
+      Key::Character("latin1".into()),
+      ElementState::Pressed,
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().encoding, Encoding::Latin1);
+    assert!(app.buffer().is_modified());
+    assert_eq!(app.indicators(), "ISO-8859-1  LF");
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(std::fs::read(&path).unwrap(), b"caf\xE9");
+  }
+
+  #[test]
+  fn save_unencodable() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    std::fs::write(&path, b"caf\xE9").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    assert_eq!(app.buffer().encoding, Encoding::Latin1);
+
+    app.handle_keyboard_input(
+      Key::Character("\u{20ac}".into()),
+      ElementState::Pressed,
+    );
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(
+      app.status.as_deref(),
+      Some("`\u{20ac}` cannot be encoded as ISO-8859-1")
+    );
+
+    assert!(app.buffer().is_modified());
+    assert_eq!(std::fs::read(&path).unwrap(), b"caf\xE9");
+  }
+
+  pub(super) fn modify(path: &Path, content: &str) {
+    std::fs::write(path, content).unwrap();
+  }
+
+  #[test]
+  fn pipe_commit() {
+    let mut app = App::pipe("foo\n".as_bytes()).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "foo\n");
+    assert_eq!(app.title(), "stdin - scratchpad");
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.exit);
+    assert!(app.prompt.is_none());
+    assert_eq!(app.finish().unwrap(), Some(b"afoo\n".to_vec()));
+  }
+
+  #[test]
+  fn pipe_lossy() {
+    let mut app = App::pipe(&b"\xC3\xA9\xC3\xA9 \xFF"[..]).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "\u{e9}\u{e9} \u{fffd}");
+
+    assert_eq!(
+      app.status.as_deref(),
+      Some(
+        "warning: stdin is not valid UTF-8, invalid bytes were replaced. \
+         Ctrl+Enter to commit, Escape to cancel"
+      )
+    );
+
+    app.close_requested();
+
+    assert_eq!(
+      app.finish().unwrap(),
+      Some("\u{e9}\u{e9} \u{fffd}".as_bytes().to_vec())
+    );
+  }
+
+  #[test]
+  fn pipe_close_commits() {
+    let mut app = App::pipe(&b"caf\xE9"[..]).unwrap();
+
+    assert_eq!(app.buffer().content.to_string(), "caf\u{e9}");
+
+    app.close_requested();
+
+    assert!(app.exit);
+    assert_eq!(app.finish().unwrap(), Some(b"caf\xE9".to_vec()));
+  }
+
+  #[test]
+  fn pipe_cancel() {
+    let mut app = App::pipe("foo".as_bytes()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.exit);
+    assert!(app.prompt.is_none());
+    assert!(matches!(app.finish(), Err(Error::Cancelled { .. })));
+  }
+
+  #[test]
+  fn pipe_commit_unencodable() {
+    let mut app = App::pipe(&b"caf\xE9"[..]).unwrap();
+
+    app.handle_keyboard_input(
+      Key::Character("\u{20ac}".into()),
+      ElementState::Pressed,
+    );
+
+    app.close_requested();
+
+    assert!(!app.exit);
+    assert!(app.status.as_ref().unwrap().contains("cannot be encoded"));
+  }
+
+  #[test]
+  fn finish_without_pipe() {
+    assert_eq!(App::new().finish().unwrap(), None);
+  }
+
+  pub(super) fn command(app: &mut App, modifiers: ModifiersState, key: &str) {
+    app.modifiers = modifiers;
+    app
+      .handle_keyboard_input(Key::Character(key.into()), ElementState::Pressed);
+    app.modifiers = ModifiersState::empty();
+  }
+
+  #[test]
+  fn new_and_cycle_buffers() {
+    let mut app = App::new();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.active(), 1);
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert_eq!(app.title(), "untitled-1 - scratchpad");
+    assert_eq!(
+      app.tabs(),
+      [
+        Tab {
+          active: false,
+          modified: true,
+          name: "untitled-0".into(),
+        },
+        Tab {
+          active: true,
+          modified: false,
+          name: "untitled-1".into(),
+        },
+      ]
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::PageDown),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::PageUp),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "b");
+  }
+
+  #[test]
+  fn open_file_replaces_pristine_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let foo = tempdir.path().join("foo.txt");
+    let bar = tempdir.path().join("bar.txt");
+
+    modify(&foo, "foo");
+    modify(&bar, "bar");
+
+    let mut app = App::open(foo.clone()).unwrap();
+
+    assert_eq!(app.buffers.len(), 1);
+
+    command(&mut app, ModifiersState::CONTROL, "o");
+
+    assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::Open);
+
+    app.prompt.as_mut().unwrap().input = bar.display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.buffer().content.to_string(), "bar");
+
+    app.open_file(foo).unwrap();
+
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.active(), 0);
+  }
+
+  #[test]
+  fn open_missing_directory_is_reported() {
+    let tempdir = TempDir::new().unwrap();
+
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "o");
+
+    app.prompt.as_mut().unwrap().input = tempdir.path().display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.status.as_ref().unwrap().starts_with("failed to read"));
+    assert_eq!(app.buffers.len(), 1);
+  }
+
+  #[test]
+  fn close_buffer() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    assert_eq!(app.buffers.len(), 3);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.active(), 1);
+    assert_eq!(app.buffer().id, 1);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    assert_eq!(app.buffers.len(), 1);
+    assert!(app.buffer().is_pristine());
+    assert!(!app.exit);
+  }
+
+  #[test]
+  fn close_modified_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    assert_eq!(
+      app.prompt.as_ref().unwrap().kind,
+      PromptKind::UnsavedChanges
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffers.len(), 2);
+    assert_eq!(app.after_save, None);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    app.prompt.as_mut().unwrap().input = path.display().to_string();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+    assert_eq!(app.buffers.len(), 1);
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffers.len(), 1);
+    assert!(!app.exit);
+  }
+
+  #[test]
+  fn switch_buffer_by_name() {
+    let tempdir = TempDir::new().unwrap();
+
+    let mut app =
+      App::scratch(Scratch::new(tempdir.path().join("scratch"))).unwrap();
+
+    app.open_file(tempdir.path().join("foo.txt")).unwrap();
+    app.open_file(tempdir.path().join("food.txt")).unwrap();
+    app.open_file(tempdir.path().join("bar.txt")).unwrap();
+
+    let switch = |app: &mut App, name: &str| {
+      command(app, ModifiersState::CONTROL, "b");
+      assert_eq!(app.prompt.as_ref().unwrap().kind, PromptKind::SwitchBuffer);
+      app.prompt.as_mut().unwrap().input = name.into();
+      app.handle_keyboard_input(
+        Key::Named(NamedKey::Enter),
+        ElementState::Pressed,
+      );
+      app.buffer().name()
+    };
+
+    assert_eq!(switch(&mut app, "scratch"), "scratch");
+    assert_eq!(switch(&mut app, "foo.txt"), "foo.txt");
+    assert_eq!(switch(&mut app, "BAR"), "bar.txt");
+
+    assert_eq!(switch(&mut app, "foo"), "bar.txt");
+    assert_eq!(app.status.as_deref(), Some("`foo` matches 2 buffers"));
+
+    assert_eq!(switch(&mut app, "baz"), "bar.txt");
+    assert_eq!(app.status.as_deref(), Some("no buffer matches `baz`"));
+  }
+
+  #[test]
+  fn quit_prompts_for_each_modified_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let foo = tempdir.path().join("foo.txt");
+    let bar = tempdir.path().join("bar.txt");
+
+    let mut app = App::open(foo.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.open_file(bar.clone()).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app
+      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+
+    app.activate(1);
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().path, Some(foo.clone()));
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    assert_eq!(std::fs::read_to_string(&foo).unwrap(), "a");
+    assert!(!app.exit);
+    assert_eq!(app.buffer().name(), "untitled-3");
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    assert!(app.exit);
+    assert!(!bar.exists());
+  }
+
+  #[test]
+  fn recover_each_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    std::fs::write(
+      tempdir.path().join("1-0.swp"),
+      "
+foo",
+    )
+    .unwrap();
+    std::fs::write(
+      tempdir.path().join("1-1.swp"),
+      "
+bar",
+    )
+    .unwrap();
+
+    let mut app = App::new();
+
+    app.set_swap(Swap::new(tempdir.path().into()).unwrap());
+
+    app
+      .handle_keyboard_input(Key::Character("r".into()), ElementState::Pressed);
+
+    app
+      .handle_keyboard_input(Key::Character("r".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_none());
+
+    assert_eq!(
+      app
+        .buffers
+        .iter()
+        .map(|buffer| buffer.content.to_string())
+        .collect::<Vec<String>>(),
+      ["bar", "foo"]
+    );
+
+    assert!(app.buffers.iter().all(Buffer::is_modified));
+  }
+
+  #[test]
+  fn control_tab_cycles_buffers() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app.handle_keyboard_input(Key::Named(NamedKey::Tab), ElementState::Pressed);
+
+    assert_eq!(app.active(), 0);
+
+    app.modifiers = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+    app.handle_keyboard_input(Key::Named(NamedKey::Tab), ElementState::Pressed);
+
+    assert_eq!(app.active(), 1);
+  }
+
+  pub(super) fn key(app: &mut App, modifiers: ModifiersState, key: NamedKey) {
+    app.modifiers = modifiers;
+    app.handle_keyboard_input(Key::Named(key), ElementState::Pressed);
+    app.modifiers = ModifiersState::empty();
+  }
+
+  #[test]
+  fn split_panes_have_independent_cursors() {
+    let mut app = App::new();
+
+    app.insert(0, "hello");
+    app.buffer_mut().cursor = 5;
+
+    command(&mut app, ModifiersState::ALT, "v");
+
+    assert_eq!(app.panes.len(), 2);
+    assert_eq!(app.focus, 1);
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowLeft);
+
+    assert_eq!(app.focus, 0);
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowRight);
+
+    assert_eq!(app.focus, 1);
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn edits_move_cursors_of_other_panes() {
+    let mut app = App::new();
+
+    app.insert(0, "abc");
+    app.buffer_mut().cursor = 2;
+
+    command(&mut app, ModifiersState::ALT, "h");
+
+    app.buffer_mut().cursor = 0;
+
+    app
+      .handle_keyboard_input(Key::Character("x".into()), ElementState::Pressed);
+
+    assert_eq!(app.panes[&0].cursor, 3);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Delete);
+
+    assert_eq!(app.panes[&0].cursor, 2);
+
+    app.buffer_mut().cursor = 3;
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+    key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+
+    assert_eq!(app.buffer().content.to_string(), "x");
+    assert_eq!(app.panes[&0].cursor, 1);
+
+    let view = app.view();
+
+    assert_eq!(view.panes.len(), 2);
+    assert_eq!(view.panes[0].lines, ["x"]);
+    assert_eq!(view.panes[0].cursor, Some((0, 1)));
+    assert_eq!(view.panes[1].lines, ["x"]);
+    assert_eq!(view.panes[1].cursor, Some((0, 1)));
+  }
+
+  #[test]
+  fn panes_show_different_buffers() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::ALT, "v");
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].lines, [""]);
+    assert_eq!(view.panes[1].lines, ["a"]);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().id, 0);
+
+    command(&mut app, ModifiersState::CONTROL, "w");
+
+    assert!(app.panes.values().all(|pane| pane.buffer == 1));
+  }
+
+  #[test]
+  fn focus_neighbor_by_direction() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::ALT, "v");
+    command(&mut app, ModifiersState::ALT, "h");
+
+    assert_eq!(app.focus, 2);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowUp);
+    assert_eq!(app.focus, 1);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowLeft);
+    assert_eq!(app.focus, 0);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowRight);
+    assert_eq!(app.focus, 1);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowUp);
+    assert_eq!(app.focus, 1);
+  }
+
+  #[test]
+  fn resize_and_close_panes() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::ALT, "v");
+
+    key(
+      &mut app,
+      ModifiersState::ALT | ModifiersState::SHIFT,
+      NamedKey::ArrowLeft,
+    );
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].rect.width, 720.0);
+    assert_eq!(view.panes[1].rect.width, 880.0);
+
+    command(&mut app, ModifiersState::ALT, "q");
+
+    assert_eq!(app.panes.len(), 1);
+    assert_eq!(app.focus, 0);
+    assert_eq!(app.view().panes[0].rect.width, 1600.0);
+
+    command(&mut app, ModifiersState::ALT, "q");
+
+    assert_eq!(app.status.as_deref(), Some("cannot close the only pane"));
+  }
+
+  #[test]
+  fn scroll_to_cursor() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 300);
+
+    for _ in 0..20 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
+
+    let rows = app.rows(app.text_area());
+
+    assert_eq!(app.panes[&0].scroll, 21 - rows);
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].lines.len(), rows);
+    assert_eq!(view.panes[0].cursor, Some((rows - 1, 0)));
+
+    app.buffer_mut().cursor = 0;
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    assert_eq!(app.panes[&0].scroll, 0);
+  }
+
+  pub(super) fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+      let key = match c {
+        ' ' => Key::Named(NamedKey::Space),
+        '\n' => Key::Named(NamedKey::Enter),
+        c => Key::Character(c.to_string().into()),
+      };
+
+      app.handle_keyboard_input(key, ElementState::Pressed);
+    }
+  }
+
+  #[test]
+  fn undo_and_redo_typing() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello world\nfoo");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "hello world\n");
+    assert_eq!(app.buffer().cursor, 12);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "hello world");
+    assert_eq!(app.buffer().cursor, 11);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert_eq!(app.buffer().cursor, 0);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.status.as_deref(), Some("nothing to undo"));
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "hello world");
+    assert_eq!(app.buffer().cursor, 11);
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "hello world\nfoo");
+    assert_eq!(app.buffer().cursor, 15);
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert_eq!(app.status.as_deref(), Some("nothing to redo"));
+  }
+
+  #[test]
+  fn cursor_movement_splits_undo_steps() {
+    let mut app = App::new();
+
+    type_text(&mut app, "ab");
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowLeft),
+      ElementState::Pressed,
+    );
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::ArrowRight),
+      ElementState::Pressed,
+    );
+
+    type_text(&mut app, "cd");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+
+    type_text(&mut app, "e");
+
+    assert_eq!(app.buffer().content.to_string(), "abe");
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "abe");
+  }
+
+  #[test]
+  fn undo_deletions() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abcdef");
+
+    app.buffer_mut().cursor = 3;
+
+    for _ in 0..2 {
+      app.handle_keyboard_input(
+        Key::Named(NamedKey::Backspace),
+        ElementState::Pressed,
+      );
+    }
+
+    for _ in 0..2 {
+      app.handle_keyboard_input(
+        Key::Named(NamedKey::Delete),
+        ElementState::Pressed,
+      );
+    }
+
+    assert_eq!(app.buffer().content.to_string(), "af");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "adef");
+    assert_eq!(app.buffer().cursor, 1);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "abcdef");
+    assert_eq!(app.buffer().cursor, 3);
+  }
+
+  #[test]
+  fn undo_to_saved_state_is_clean() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    fs::write(&path, "foo").unwrap();
+
+    let mut app = App::open(path).unwrap();
+
+    type_text(&mut app, "bar");
+
+    assert!(app.buffer().is_dirty());
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert!(!app.buffer().is_dirty());
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert!(app.buffer().is_dirty());
+
+    command(&mut app, ModifiersState::CONTROL, "s");
+
+    assert!(!app.buffer().is_dirty());
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "foo");
+    assert!(app.buffer().is_dirty());
+  }
+
+  #[test]
+  fn undo_moves_cursors_of_other_panes() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abc");
+
+    command(&mut app, ModifiersState::ALT, "v");
+
+    app.buffer_mut().cursor = 0;
+
+    type_text(&mut app, "xy");
+
+    assert_eq!(app.panes[&0].cursor, 5);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "abc");
+    assert_eq!(app.panes[&0].cursor, 3);
+  }
+
+  #[test]
+  fn undo_branches() {
+    let mut app = App::new();
+
+    type_text(&mut app, "a");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    type_text(&mut app, "b");
+
+    command(&mut app, ModifiersState::ALT, "[");
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+    assert_eq!(app.status.as_deref(), Some("branch 1 of 2"));
+
+    command(&mut app, ModifiersState::ALT, "]");
+
+    assert_eq!(app.buffer().content.to_string(), "b");
+
+    command(&mut app, ModifiersState::ALT, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+
+    command(&mut app, ModifiersState::ALT, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "");
+
+    command(&mut app, ModifiersState::ALT | ModifiersState::SHIFT, "Z");
+
+    assert_eq!(app.buffer().content.to_string(), "a");
+
+    command(&mut app, ModifiersState::ALT | ModifiersState::SHIFT, "Z");
+    command(&mut app, ModifiersState::ALT | ModifiersState::SHIFT, "Z");
+
+    assert_eq!(app.status.as_deref(), Some("already at the newest change"));
+  }
+
+  #[test]
+  fn go_back_prompt() {
+    let mut app = App::new();
+
+    type_text(&mut app, "a");
+
+    command(&mut app, ModifiersState::ALT, "g");
+
+    app.prompt.as_mut().unwrap().input = "soon".into();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.status.as_deref(), Some("invalid duration `soon`"));
+
+    command(&mut app, ModifiersState::ALT, "g");
+
+    app.prompt.as_mut().unwrap().input = "1d".into();
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "");
+  }
+
+  #[test]
+  fn undo_history_survives_reopening() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    type_text(&mut app, "foo");
+
+    command(&mut app, ModifiersState::CONTROL, "s");
+
+    assert!(tempdir.path().join(".foo.txt.undo").exists());
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    assert!(!app.buffer().is_dirty());
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "");
+    assert!(app.buffer().is_dirty());
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "Z",
+    );
+
+    assert!(!app.buffer().is_dirty());
+
+    modify(&path, "bar");
+
+    let mut app = App::open(path).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "bar");
+    assert_eq!(app.status.as_deref(), Some("nothing to undo"));
+  }
+
+  #[test]
+  fn edit_grapheme_clusters() {
+    let mut app = App::new();
+
+    type_text(&mut app, "e\u{301}\u{1F469}\u{200D}\u{1F4BB}x");
+
+    assert_eq!(app.buffer().cursor, 6);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().cursor, 2);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+
+    assert_eq!(
+      app.buffer().content.to_string(),
+      "\u{1F469}\u{200D}\u{1F4BB}x"
+    );
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Delete);
+
+    assert_eq!(app.buffer().content.to_string(), "x");
+    assert_eq!(app.view().panes[0].cursor, Some((0, 0)));
+  }
+
+  proptest! {
+    #[test]
+    fn typing_and_deleting_arbitrary_text(text in "\\PC{0,64}") {
+      let graphemes = text.graphemes(true).count();
+
+      let mut app = App::new();
+
+      type_text(&mut app, &text);
+
+      prop_assert_eq!(app.buffer().content.to_string(), text.clone());
+      prop_assert_eq!(app.buffer().cursor, text.chars().count());
+
+      for _ in 0..graphemes {
+        key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+      }
+
+      prop_assert_eq!(app.buffer().cursor, 0);
+
+      for _ in 0..graphemes {
+        key(&mut app, ModifiersState::empty(), NamedKey::ArrowRight);
+      }
+
+      prop_assert_eq!(app.buffer().cursor, text.chars().count());
+
+      for _ in 0..graphemes {
+        prop_assert!(app.buffer().content.is_grapheme_boundary(app.buffer().cursor));
+        key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+      }
+
+      prop_assert_eq!(app.buffer().content.len_chars(), 0);
+
+      command(&mut app, ModifiersState::CONTROL, "z");
+
+      prop_assert_eq!(app.buffer().content.to_string(), text);
+
+      app.buffer_mut().cursor = 0;
+
+      for _ in 0..graphemes {
+        key(&mut app, ModifiersState::empty(), NamedKey::Delete);
+      }
+
+      prop_assert_eq!(app.buffer().content.len_chars(), 0);
+    }
+  }
+
+  #[test]
+  fn shift_arrows_select() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello");
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().selection(), Some(3..5));
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowRight);
+
+    assert_eq!(app.buffer().selection(), Some(4..5));
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
+
+    assert_eq!(app.buffer().selection(), Some(0..5));
+    assert_eq!(app.buffer().cursor, 0);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowRight);
+
+    assert_eq!(app.buffer().selection(), None);
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().selection(), None);
+    assert_eq!(app.buffer().cursor, 4);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::End);
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
+
+    assert_eq!(app.buffer().selection(), None);
+    assert_eq!(app.buffer().cursor, 0);
+  }
+
+  #[test]
+  fn typing_replaces_selection() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello world");
+
+    for _ in 0..5 {
+      key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    }
+
+    type_text(&mut app, "there");
+
+    assert_eq!(app.buffer().content.to_string(), "hello there");
+    assert_eq!(app.buffer().cursor, 11);
+    assert_eq!(app.buffer().selection(), None);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
+    key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+
+    assert_eq!(app.buffer().content.to_string(), "\n");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "hello there");
+  }
+
+  #[test]
+  fn backspace_and_delete_remove_selection() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abcdef");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+
+    assert_eq!(app.buffer().content.to_string(), "abcf");
+    assert_eq!(app.buffer().cursor, 3);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
+    key(&mut app, ModifiersState::empty(), NamedKey::Delete);
+
+    assert_eq!(app.buffer().content.to_string(), "f");
+    assert_eq!(app.buffer().cursor, 0);
+  }
+
+  #[test]
+  fn select_all() {
+    let mut app = App::new();
+
+    type_text(&mut app, "one\ntwo");
+
+    command(&mut app, ModifiersState::CONTROL, "a");
+
+    assert_eq!(app.buffer().selection(), Some(0..7));
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "x");
+  }
+
+  #[test]
+  fn view_selection() {
+    let mut app = App::new();
+
+    type_text(&mut app, "one\ntwo\nthree");
+
+    app.buffer_mut().anchor = Some(1);
+    app.buffer_mut().cursor = 10;
+
+    assert_eq!(
+      app.view().panes[0].selection,
+      [(0, 1..4), (1, 0..4), (2, 0..2)]
+    );
+
+    app.buffer_mut().anchor = Some(4);
+    app.buffer_mut().cursor = 3;
+
+    assert_eq!(app.view().panes[0].selection, [(0, 3..4)]);
+
+    app.buffer_mut().cursor = 8;
+
+    assert_eq!(app.view().panes[0].selection, [(1, 0..4)]);
+  }
+
+  #[test]
+  fn panes_keep_selections() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abc");
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+
+    command(&mut app, ModifiersState::ALT, "v");
+
+    assert_eq!(app.buffer().selection(), Some(2..3));
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
+
+    type_text(&mut app, "xy");
+
+    assert_eq!(app.view().panes[0].selection, [(0, 4..5)]);
+
+    key(&mut app, ModifiersState::ALT, NamedKey::ArrowLeft);
+
+    assert_eq!(app.buffer().selection(), Some(4..5));
+  }
+
+  #[test]
+  fn add_cursors_above_and_below() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abc\ndef\ngh");
+
+    app.buffer_mut().cursor = 5;
+
+    key(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::ALT,
+      NamedKey::ArrowUp,
+    );
+
+    key(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::ALT,
+      NamedKey::ArrowDown,
+    );
+
+    assert_eq!(app.buffer().cursor, 9);
+    assert_eq!(
+      app.buffer().cursors,
+      [
+        Selection {
+          goal: Some(1),
+          ..Selection::cursor(1)
+        },
+        Selection::cursor(5)
+      ]
+    );
+
+    assert_eq!(app.view().panes[0].cursor, Some((2, 1)));
+    assert_eq!(app.view().panes[0].other_cursors, [(0, 1), (1, 1)]);
+
+    type_text(&mut app, "X");
+
+    assert_eq!(app.buffer().content.to_string(), "aXbc\ndXef\ngXh");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Backspace);
+
+    assert_eq!(app.buffer().content.to_string(), "abc\ndef\ngh");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Escape);
+
+    assert!(!app.exit);
+    assert!(app.buffer().cursors.is_empty());
+    assert_eq!(app.buffer().cursor, 9);
+  }
+
+  #[test]
+  fn undo_restores_every_selection() {
+    let mut app = App::new();
+
+    type_text(&mut app, "foo bar foo");
+
+    app.buffer_mut().cursor = 1;
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    let selections = app.buffer().selections();
+
+    assert_eq!(selections.len(), 2);
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "x bar x");
+
+    let after = app.buffer().selections();
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "foo bar foo");
+    assert_eq!(app.buffer().selections(), selections);
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "z",
+    );
+
+    assert_eq!(app.buffer().selections(), after);
+  }
+
+  #[test]
+  fn cursors_merge() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abcdef");
+
+    app.buffer_mut().cursor = 3;
+    app.buffer_mut().cursors = vec![Selection::cursor(4)];
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Delete);
+
+    assert_eq!(app.buffer().content.to_string(), "abcf");
+    assert_eq!(app.buffer().cursor, 3);
+    assert!(app.buffer().cursors.is_empty());
+
+    app.buffer_mut().cursors = vec![Selection::cursor(1)];
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
+
+    assert_eq!(app.buffer().selection(), Some(0..3));
+    assert!(app.buffer().cursors.is_empty());
+  }
+
+  #[test]
+  fn select_next_occurrence() {
+    let mut app = App::new();
+
+    type_text(&mut app, "foo bar foo baz foo");
+
+    app.buffer_mut().cursor = 9;
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    assert_eq!(app.buffer().selection(), Some(8..11));
+    assert!(app.buffer().cursors.is_empty());
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    assert_eq!(app.buffer().selection(), Some(16..19));
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    assert_eq!(app.buffer().selection(), Some(0..3));
+    assert_eq!(app.buffer().cursors.len(), 2);
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    assert_eq!(app.status.as_deref(), Some("no more occurrences"));
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "x bar x baz x");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "foo bar foo baz foo");
+  }
+
+  #[test]
+  fn select_all_occurrences() {
+    let mut app = App::new();
+
+    type_text(&mut app, "ab abc ab");
+
+    app.buffer_mut().cursor = 8;
+
+    command(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      "L",
+    );
+
+    assert_eq!(app.buffer().selection(), Some(7..9));
+    assert_eq!(app.buffer().cursors.len(), 2);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowRight);
+    type_text(&mut app, "!");
+
+    assert_eq!(app.buffer().content.to_string(), "ab! ab!c ab!");
   }
 
-  fn about_to_wait(&mut self, _: &ActiveEventLoop) {
-    if let Some(window) = &self.window {
-      window.request_redraw();
+  #[test]
+  fn copy_cut_and_paste() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello world");
+
+    command(&mut app, ModifiersState::CONTROL, "c");
+
+    assert_eq!(app.status.as_deref(), Some("nothing selected"));
+
+    for _ in 0..5 {
+      key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
     }
-  }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+    command(&mut app, ModifiersState::CONTROL, "c");
+
+    assert_eq!(app.clipboard.get().unwrap().as_deref(), Some("world"));
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
+    command(&mut app, ModifiersState::CONTROL, "v");
+
+    assert_eq!(app.buffer().content.to_string(), "worldhello world");
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
+    command(&mut app, ModifiersState::CONTROL, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "hello world");
+    assert_eq!(app.clipboard.get().unwrap().as_deref(), Some("world"));
+  }
 
   #[test]
-  fn insert_character() {
+  fn paste_is_one_edit() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    type_text(&mut app, "ab");
 
-    assert_eq!(app.editor_content.to_string(), "a");
-    assert_eq!(app.cursor_position, 1);
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    let text = "line\n".repeat(100_000);
+
+    app.clipboard.set(text.clone()).unwrap();
+
+    let revision = app.buffer().revision;
+
+    command(&mut app, ModifiersState::CONTROL, "v");
 
-    assert_eq!(app.editor_content.to_string(), "ab");
-    assert_eq!(app.cursor_position, 2);
+    assert_eq!(app.buffer().revision, revision + 1);
+    assert_eq!(app.buffer().content.to_string(), format!("a{text}b"));
+    assert_eq!(app.buffer().cursor, 1 + text.len());
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+    assert_eq!(app.buffer().cursor, 1);
   }
 
   #[test]
-  fn backspace() {
+  fn paste_converts_line_endings() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    app.buffer_mut().line_ending = LineEnding::Crlf;
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    app.clipboard.set("a\nb\rc".into()).unwrap();
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Backspace),
-      ElementState::Pressed,
-    );
+    command(&mut app, ModifiersState::CONTROL, "v");
 
-    assert_eq!(app.editor_content.to_string(), "a");
-    assert_eq!(app.cursor_position, 1);
+    assert_eq!(app.buffer().content.to_string(), "a\r\nb\r\nc");
   }
 
   #[test]
-  fn delete_character() {
+  fn paste_line_per_cursor() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    type_text(&mut app, "a\nb\nc");
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    app.buffer_mut().cursor = 1;
+    app.buffer_mut().cursors = vec![Selection::cursor(3), Selection::cursor(5)];
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowLeft),
-      ElementState::Pressed,
-    );
+    command(&mut app, ModifiersState::CONTROL, "c");
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Delete),
-      ElementState::Pressed,
+    assert_eq!(app.status.as_deref(), Some("nothing selected"));
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+    command(&mut app, ModifiersState::CONTROL, "c");
+
+    assert_eq!(app.clipboard.get().unwrap().as_deref(), Some("a\nb\nc"));
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowRight);
+
+    app.clipboard.set("1\n2\n3\n".into()).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "v");
+
+    assert_eq!(app.buffer().content.to_string(), "a1\nb2\nc3");
+
+    app.clipboard.set("12".into()).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "v");
+
+    assert_eq!(app.buffer().content.to_string(), "a112\nb212\nc312");
+  }
+
+  #[test]
+  fn up_and_down_keep_column() {
+    let mut app = App::new();
+
+    type_text(
+      &mut app,
+      "abcdef\nab\n\u{1F469}\u{200D}\u{1F4BB}e\u{301}xyz",
     );
 
-    assert_eq!(app.editor_content.to_string(), "a");
-    assert_eq!(app.cursor_position, 1);
+    app.buffer_mut().cursor = 5;
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().cursor, 9);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().cursor, 18);
+    assert_eq!(app.view().panes[0].cursor, Some((2, 8)));
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
+
+    assert_eq!(app.buffer().cursor, 5);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
+
+    assert_eq!(app.buffer().cursor, 0);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().cursor, 9);
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    for _ in 0..4 {
+      key(&mut app, ModifiersState::empty(), NamedKey::ArrowRight);
+    }
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().selection(), Some(4..9));
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().selection(), Some(4..17));
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowDown);
+
+    assert_eq!(app.buffer().selection(), Some(4..18));
   }
 
   #[test]
-  fn cursor_movement() {
+  fn home_and_end_stay_on_line() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    type_text(&mut app, "one\n  two three\nfour");
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    app.buffer_mut().cursor = 10;
 
-    app
-      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowLeft),
-      ElementState::Pressed,
-    );
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowLeft),
-      ElementState::Pressed,
-    );
+    assert_eq!(app.buffer().cursor, 6);
 
-    assert_eq!(app.cursor_position, 1);
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowRight),
-      ElementState::Pressed,
+    assert_eq!(app.buffer().cursor, 4);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
+
+    assert_eq!(app.buffer().cursor, 6);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::End);
+
+    assert_eq!(app.buffer().selection(), Some(6..15));
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    assert_eq!(app.buffer().cursor, 0);
+    assert_eq!(app.buffer().selection(), None);
+
+    key(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      NamedKey::End,
     );
 
-    assert_eq!(app.cursor_position, 2);
+    assert_eq!(app.buffer().selection(), Some(0..20));
   }
 
   #[test]
-  fn home_end_keys() {
+  fn crlf_lines() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    app.buffer_mut().line_ending = LineEnding::Crlf;
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    type_text(&mut app, "abc\nd\nefg");
 
-    app
-      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
 
-    app
-      .handle_keyboard_input(Key::Named(NamedKey::Home), ElementState::Pressed);
+    assert_eq!(app.buffer().cursor, 6);
 
-    assert_eq!(app.cursor_position, 0);
+    key(&mut app, ModifiersState::empty(), NamedKey::End);
 
-    app.handle_keyboard_input(Key::Named(NamedKey::End), ElementState::Pressed);
+    assert_eq!(app.buffer().cursor, 6);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
 
-    assert_eq!(app.cursor_position, 3);
+    assert_eq!(app.buffer().cursor, 1);
   }
 
   #[test]
-  fn enter_key() {
+  fn move_by_word() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    type_text(&mut app, "foo.bar(baz) qux\n  end");
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Enter),
-      ElementState::Pressed,
-    );
+    app.buffer_mut().cursor = 0;
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    let mut stops = Vec::new();
+
+    for _ in 0..9 {
+      key(&mut app, ModifiersState::CONTROL, NamedKey::ArrowRight);
+      stops.push(app.buffer().cursor);
+    }
+
+    assert_eq!(stops, [3, 4, 7, 8, 11, 12, 16, 22, 22]);
 
-    assert_eq!(app.editor_content.to_string(), "a\nb");
-    assert_eq!(app.cursor_position, 3);
+    let mut stops = Vec::new();
+
+    for _ in 0..9 {
+      key(&mut app, ModifiersState::CONTROL, NamedKey::ArrowLeft);
+      stops.push(app.buffer().cursor);
+    }
+
+    assert_eq!(stops, [19, 13, 11, 8, 7, 4, 3, 0, 0]);
+
+    key(
+      &mut app,
+      ModifiersState::CONTROL | ModifiersState::SHIFT,
+      NamedKey::ArrowRight,
+    );
+
+    assert_eq!(app.buffer().selection(), Some(0..3));
   }
 
   #[test]
-  fn space_key() {
+  fn delete_by_word() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    type_text(&mut app, "foo.bar(baz) qux\n  end");
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Space),
-      ElementState::Pressed,
-    );
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Backspace);
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    assert_eq!(app.buffer().content.to_string(), "foo.bar(baz) qux\n  ");
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Backspace);
 
-    assert_eq!(app.editor_content.to_string(), "a b");
-    assert_eq!(app.cursor_position, 3);
+    assert_eq!(app.buffer().content.to_string(), "foo.bar(baz) ");
+
+    app.buffer_mut().cursor = 0;
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Delete);
+
+    assert_eq!(app.buffer().content.to_string(), ".bar(baz) ");
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Delete);
+
+    assert_eq!(app.buffer().content.to_string(), "bar(baz) ");
   }
 
   #[test]
-  fn insert_at_cursor_position() {
+  fn custom_word_separators() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    app.set_word_separators(String::new());
 
-    app
-      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+    type_text(&mut app, "foo.bar(baz)");
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowLeft),
-      ElementState::Pressed,
-    );
+    app.buffer_mut().cursor = 0;
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    key(&mut app, ModifiersState::CONTROL, NamedKey::ArrowRight);
 
-    assert_eq!(app.editor_content.to_string(), "abc");
-    assert_eq!(app.cursor_position, 2);
+    assert_eq!(app.buffer().cursor, 7);
+
+    command(&mut app, ModifiersState::CONTROL, "d");
+
+    assert_eq!(app.buffer().selection(), Some(0..7));
   }
 
   #[test]
-  fn multiple_characters_deletion() {
+  fn scroll_margin() {
     let mut app = App::new();
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    app.size = PhysicalSize::new(800, 600);
 
-    app
-      .handle_keyboard_input(Key::Character("b".into()), ElementState::Pressed);
+    let rows = app.rows(app.text_area());
 
-    app
-      .handle_keyboard_input(Key::Character("c".into()), ElementState::Pressed);
+    for _ in 0..40 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
 
-    app
-      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
 
-    app
-      .handle_keyboard_input(Key::Character("e".into()), ElementState::Pressed);
+    for _ in 0..rows {
+      key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+    }
 
-    app
-      .handle_keyboard_input(Key::Character("f".into()), ElementState::Pressed);
+    assert_eq!(app.panes[&0].scroll, 4);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Backspace),
-      ElementState::Pressed,
-    );
+    for _ in 0..rows - 6 {
+      key(&mut app, ModifiersState::empty(), NamedKey::ArrowUp);
+    }
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Backspace),
-      ElementState::Pressed,
-    );
+    assert_eq!(app.panes[&0].scroll, 3);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Backspace),
-      ElementState::Pressed,
-    );
+    app.set_scroll_margin(0);
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    for _ in 0..rows {
+      key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+    }
 
-    assert_eq!(app.editor_content.to_string(), "abc");
-    assert_eq!(app.cursor_position, 3);
+    assert_eq!(app.panes[&0].scroll, 1);
   }
 
   #[test]
-  fn boundary_conditions() {
+  fn scroll_horizontally() {
     let mut app = App::new();
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Backspace),
-      ElementState::Pressed,
-    );
+    app.size = PhysicalSize::new(800, 600);
 
-    assert_eq!(app.editor_content.to_string(), "");
-    assert_eq!(app.cursor_position, 0);
+    let columns = app.columns(app.text_area());
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::Delete),
-      ElementState::Pressed,
-    );
+    type_text(&mut app, &"x".repeat(100));
 
-    assert_eq!(app.editor_content.to_string(), "");
-    assert_eq!(app.cursor_position, 0);
+    assert_eq!(app.panes[&0].scroll_column, 101 - columns);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowLeft),
-      ElementState::Pressed,
-    );
+    let view = app.view();
 
-    assert_eq!(app.cursor_position, 0);
+    assert_eq!(view.panes[0].lines, ["x".repeat(columns - 1)]);
+    assert_eq!(view.panes[0].cursor, Some((0, columns - 1)));
 
-    app
-      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+    key(&mut app, ModifiersState::SHIFT, NamedKey::Home);
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowRight),
-      ElementState::Pressed,
-    );
+    assert_eq!(app.panes[&0].scroll_column, 0);
 
-    assert_eq!(app.cursor_position, 1);
+    let view = app.view();
 
-    app.handle_keyboard_input(
-      Key::Named(NamedKey::ArrowRight),
-      ElementState::Pressed,
-    );
+    assert_eq!(view.panes[0].lines, ["x".repeat(100)]);
+    assert_eq!(view.panes[0].selection, [(0, 0..100)]);
+  }
+
+  #[test]
+  fn page_up_and_down() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 600);
+
+    let rows = app.rows(app.text_area());
+
+    for _ in 0..99 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
 
-    assert_eq!(app.cursor_position, 1);
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::PageDown);
+
+    assert_eq!(app.buffer().cursor, rows - 1);
+    assert_eq!(app.panes[&0].scroll, rows - 4);
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::PageDown);
+
+    assert_eq!(app.buffer().selection(), Some(rows - 1..2 * (rows - 1)));
+    assert_eq!(app.panes[&0].scroll, 2 * (rows - 1) - 3);
+
+    key(&mut app, ModifiersState::empty(), NamedKey::PageUp);
+    key(&mut app, ModifiersState::empty(), NamedKey::PageUp);
+    key(&mut app, ModifiersState::empty(), NamedKey::PageUp);
+
+    assert_eq!(app.buffer().cursor, 0);
+    assert_eq!(app.panes[&0].scroll, 0);
   }
 
   #[test]
-  fn insert_multi_char_string() {
+  fn scroll_without_moving_cursor() {
     let mut app = App::new();
 
-    app.handle_keyboard_input(
-      Key::Character("hello".into()),
-      ElementState::Pressed,
-    );
+    app.size = PhysicalSize::new(800, 600);
+
+    let rows = app.rows(app.text_area());
+
+    for _ in 0..99 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::ArrowUp);
+
+    assert_eq!(app.panes[&0].scroll, 0);
+
+    for _ in 0..5 {
+      key(&mut app, ModifiersState::CONTROL, NamedKey::ArrowDown);
+    }
+
+    assert_eq!(app.panes[&0].scroll, 5);
+    assert_eq!(app.buffer().cursor, 0);
+    assert_eq!(app.view().panes[0].cursor, None);
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.panes[&0].scroll, 0);
+
+    app.buffer_mut().cursor = 51;
+
+    command(&mut app, ModifiersState::CONTROL, "l");
 
-    assert_eq!(app.editor_content.to_string(), "hello");
-    assert_eq!(app.cursor_position, 5);
+    assert_eq!(app.panes[&0].scroll, 50 - rows / 2);
   }
 }
@@ -0,0 +1,392 @@
+use super::*;
+
+impl App {
+  /// Respond to changes to files on disk, once any open prompt has been
+  /// answered. Clean buffers are reloaded, while modified buffers ask whether
+  /// to reload, keep the buffer, or show a diff.
+  pub(super) fn check_file_changed(&mut self) {
+    while self.prompt.is_none() {
+      let Some(index) =
+        self.buffers.iter().position(|buffer| buffer.file_changed)
+      else {
+        return;
+      };
+
+      let buffer = &mut self.buffers[index];
+
+      buffer.file_changed = false;
+
+      let Some(path) = buffer.path.clone() else {
+        continue;
+      };
+
+      let stamp = Stamp::of(&path);
+
+      if stamp == buffer.disk_stamp {
+        continue;
+      }
+
+      if stamp.is_none() {
+        buffer.disk_stamp = None;
+        self.status = Some(format!("`{}` was deleted on disk", path.display()));
+      } else if buffer.is_modified() {
+        self.activate(index);
+        self.prompt = Some(Prompt::new(PromptKind::FileChanged(path), ""));
+      } else {
+        self.reload(index);
+      }
+    }
+  }
+
+  pub(super) fn file_changed(&mut self, choice: &str) {
+    match choice {
+      "d" => {
+        match self.diff_with_disk() {
+          Ok(diff) => {
+            self.diff = Some(diff);
+            self.diff_scroll = 0;
+          }
+          Err(error) => self.status = Some(error.summary()),
+        }
+
+        if let Some(path) = self.buffer().path.clone() {
+          self.prompt = Some(Prompt::new(PromptKind::FileChanged(path), ""));
+        }
+      }
+      "r" => {
+        self.diff = None;
+        self.reload(self.active());
+      }
+      _ => {
+        self.diff = None;
+        let buffer = self.buffer_mut();
+        buffer.disk_stamp = buffer.path.as_deref().and_then(Stamp::of);
+      }
+    }
+  }
+
+  /// Scroll the diff shown in the focused pane by `lines`, or up if
+  /// negative, stopping with its last line at the bottom of the pane.
+  pub(super) fn scroll_diff(&mut self, lines: isize) {
+    let Some(diff) = &self.diff else {
+      return;
+    };
+
+    let rows = self.pane_rect().map_or(1, |rect| self.rows(rect));
+
+    self.diff_scroll = self
+      .diff_scroll
+      .saturating_add_signed(lines)
+      .min(diff.lines().count().saturating_sub(rows));
+  }
+
+  pub(super) fn diff_with_disk(&self) -> Result<String> {
+    let buffer = self.buffer();
+
+    let Some(path) = &buffer.path else {
+      return Ok(String::new());
+    };
+
+    let disk = Buffer::read_file(path)?
+      .map(|(_, decoded)| decoded.text)
+      .unwrap_or_default();
+
+    let content = buffer.content.to_string();
+
+    Ok(
+      TextDiff::from_lines(&disk, &content)
+        .unified_diff()
+        .header(&path.display().to_string(), "buffer")
+        .to_string(),
+    )
+  }
+
+  pub(super) fn reload(&mut self, index: usize) {
+    let buffer = &mut self.buffers[index];
+
+    let Some(path) = buffer.path.clone() else {
+      return;
+    };
+
+    self.status = Some(match buffer.reload() {
+      Ok(true) => format!(
+        "warning: `{}` is not valid {}, invalid bytes were replaced",
+        path.display(),
+        buffer.encoding
+      ),
+      Ok(false) => format!("Reloaded {}", path.display()),
+      Err(error) => error.summary(),
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::app::tests::{command, key, modify, type_text},
+    tempfile::TempDir,
+  };
+
+  #[test]
+  fn external_change_reloads_clean_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo\nbar\nbaz");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app.buffer_mut().cursor = 6;
+
+    modify(&path, "foo\nb\nbaz\nqux");
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.buffer().content.to_string(), "foo\nb\nbaz\nqux");
+    assert_eq!(app.buffer().cursor, 5);
+    assert!(!app.buffer().is_modified());
+  }
+
+  #[test]
+  fn external_change_to_other_file_is_ignored() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    modify(&path, "foobar");
+
+    app.handle_user_event(UserEvent::FileChanged(tempdir.path().join("bar")));
+
+    assert_eq!(app.buffer().content.to_string(), "foo");
+  }
+
+  #[test]
+  fn own_save_is_not_an_external_change() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    app.modifiers = ModifiersState::CONTROL;
+
+    app
+      .handle_keyboard_input(Key::Character("s".into()), ElementState::Pressed);
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert!(app.prompt.is_none());
+    assert_eq!(app.status.unwrap(), format!("Saved {}", path.display()));
+  }
+
+  #[test]
+  fn external_change_prompts_for_modified_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo\n");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    modify(&path, "foo\nbar\n");
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert_eq!(
+      app.prompt.as_ref().unwrap().kind,
+      PromptKind::FileChanged(path.clone())
+    );
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_some());
+    assert!(app.diff.as_ref().unwrap().contains("-foo\n-bar\n+afoo\n"));
+
+    app
+      .handle_keyboard_input(Key::Character("k".into()), ElementState::Pressed);
+
+    assert!(app.prompt.is_none());
+    assert!(app.diff.is_none());
+    assert_eq!(app.buffer().content.to_string(), "afoo\n");
+    assert!(app.buffer().is_modified());
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert!(app.prompt.is_none());
+  }
+
+  #[test]
+  fn scroll_diff() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    type_text(&mut app, "a\n");
+
+    modify(&path, &"b\n".repeat(200));
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    app
+      .handle_keyboard_input(Key::Character("d".into()), ElementState::Pressed);
+
+    let rows = app.rows(app.pane_rect().unwrap());
+
+    let lines = app.diff.as_ref().unwrap().lines().count();
+
+    assert!(lines > rows);
+
+    assert_eq!(
+      app.view().panes[0].lines[0],
+      format!("--- {}", path.display())
+    );
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowDown);
+
+    assert_eq!(app.view().panes[0].lines[0], "+++ buffer");
+
+    for _ in 0..10 {
+      key(&mut app, ModifiersState::empty(), NamedKey::PageDown);
+    }
+
+    assert_eq!(app.diff_scroll, lines - rows);
+    assert_eq!(app.view().panes[0].lines.last().unwrap(), "+a");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::PageUp);
+
+    assert_eq!(app.diff_scroll, lines - 2 * rows);
+    assert!(app.prompt.is_some());
+  }
+
+  #[test]
+  fn external_change_reload_modified_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app
+      .handle_keyboard_input(Key::Character("a".into()), ElementState::Pressed);
+
+    modify(&path, "bar\n");
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    app
+      .handle_keyboard_input(Key::Character("r".into()), ElementState::Pressed);
+
+    assert_eq!(app.buffer().content.to_string(), "bar\n");
+    assert!(!app.buffer().is_modified());
+  }
+
+  #[test]
+  fn external_change_waits_for_open_prompt() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    app.modifiers = ModifiersState::ALT;
+
+    app
+      .handle_keyboard_input(Key::Character("e".into()), ElementState::Pressed);
+
+    modify(&path, "foobar");
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert_eq!(app.buffer().content.to_string(), "foo");
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert_eq!(app.buffer().content.to_string(), "foobar");
+  }
+
+  #[test]
+  fn external_deletion() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    modify(&path, "foo");
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+
+    app.handle_user_event(UserEvent::FileChanged(path.clone()));
+
+    assert_eq!(app.buffer().content.to_string(), "foo");
+    assert!(app.status.unwrap().contains("was deleted on disk"));
+  }
+
+  #[test]
+  fn external_change_reloads_background_buffer() {
+    let tempdir = TempDir::new().unwrap();
+
+    let foo = tempdir.path().join("foo.txt");
+
+    modify(&foo, "foo");
+
+    let mut app = App::open(foo.clone()).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    modify(&foo, "foobar");
+
+    app.handle_user_event(UserEvent::FileChanged(foo));
+
+    assert_eq!(app.active(), 1);
+    assert_eq!(app.buffers[0].content.to_string(), "foobar");
+  }
+
+  #[test]
+  fn undo_reload() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    fs::write(&path, "foo").unwrap();
+
+    let mut app = App::open(path.clone()).unwrap();
+
+    modify(&path, "bar");
+
+    app.handle_user_event(UserEvent::FileChanged(path));
+
+    assert_eq!(app.buffer().content.to_string(), "bar");
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "foo");
+    assert!(app.buffer().is_dirty());
+  }
+}
@@ -0,0 +1,437 @@
+use super::*;
+
+impl App {
+  /// Show the text being composed with an input method at the cursor, and
+  /// insert it like typing once it is committed.
+  pub(super) fn handle_ime(&mut self, ime: Ime) {
+    match ime {
+      Ime::Enabled => {}
+      // The prompt and finder have no room for composition, so their
+      // text only shows once it is committed.
+      Ime::Preedit(text, cursor) => {
+        if self.prompt.is_some() || self.finder.is_some() {
+          return;
+        }
+
+        self.preedit = (!text.is_empty()).then_some((text, cursor));
+        self.scroll_to_cursor();
+      }
+      Ime::Commit(text) => {
+        self.preedit = None;
+
+        let key = Key::Character(text.as_str().into());
+
+        if self.prompt.is_some() {
+          self.handle_prompt_input(&key);
+          return;
+        }
+
+        if self.finder.is_some() {
+          self.handle_finder_input(&key);
+          return;
+        }
+
+        self.status = None;
+
+        self.replace_selections(&text);
+
+        self.buffer_mut().end_transaction(Some(Group::Type));
+
+        self.scroll_to_cursor();
+      }
+      Ime::Disabled => self.preedit = None,
+    }
+  }
+
+  pub(super) fn handle_command(&mut self, key: &str) {
+    let control = self.modifiers.control_key();
+
+    let shift = self.modifiers.shift_key();
+
+    match key.to_lowercase().as_str() {
+      "b" if control => {
+        self.prompt = Some(Prompt::new(PromptKind::SwitchBuffer, ""));
+      }
+      "e" if !control => {
+        self.prompt = Some(Prompt::new(PromptKind::Encoding, ""));
+      }
+      "h" if !control => self.split_pane(Orientation::Horizontal),
+      "l" if control && shift => self.select_all_occurrences(),
+      "l" if control => self.scroll_to_center(),
+      "l" if !control => {
+        self.prompt = Some(Prompt::new(PromptKind::LineEnding, ""));
+      }
+      "g" if !control => {
+        self.prompt = Some(Prompt::new(PromptKind::GoBack, ""));
+      }
+      "a" if control => self.select_all(),
+      "c" if control => {
+        self.copy();
+      }
+      "d" if control => self.select_next_occurrence(),
+      "n" if control => self.new_buffer(),
+      "o" if control => {
+        self.prompt = Some(Prompt::new(PromptKind::Open, ""));
+      }
+      "p" if control => self.open_finder(),
+      "q" if !control => self.close_pane(),
+      "s" if control && shift => self.save_as(),
+      "s" if control => self.save(),
+      "v" if control => self.paste(),
+      "v" if !control => self.split_pane(Orientation::Vertical),
+      "w" if control => self.close_buffer(),
+      "x" if control => self.cut(),
+      "z" if control && shift => self.redo(),
+      "z" if control => self.undo(),
+      "z" if !control && shift => self.later(),
+      "z" if !control => self.earlier(),
+      "[" if !control => self.switch_branch(-1),
+      "]" if !control => self.switch_branch(1),
+      _ => {}
+    }
+  }
+
+  /// Handle `key`, which is `unmodified` without the modifiers held. AltGr,
+  /// which Windows reports as Control and Alt together, types a character
+  /// other than the key's own, which is text rather than a command, so the
+  /// modifiers are dropped for it.
+  pub(super) fn handle_key_event(
+    &mut self,
+    key: Key,
+    unmodified: &Key,
+    state: ElementState,
+  ) {
+    if self.modifiers.control_key()
+      && self.modifiers.alt_key()
+      && let (Key::Character(c), Key::Character(unmodified)) =
+        (&key, unmodified)
+      && c.to_lowercase() != unmodified.to_lowercase()
+    {
+      let modifiers = mem::take(&mut self.modifiers);
+      self.handle_keyboard_input(key, state);
+      self.modifiers = modifiers;
+    } else {
+      self.handle_keyboard_input(key, state);
+    }
+  }
+
+  pub(super) fn handle_keyboard_input(
+    &mut self,
+    key: Key,
+    state: ElementState,
+  ) {
+    // While composing, keys belong to the input method.
+    if state != ElementState::Pressed || self.preedit.is_some() {
+      return;
+    }
+
+    if self.prompt.is_some() {
+      self.handle_prompt_input(&key);
+      return;
+    }
+
+    if self.finder.is_some() {
+      self.handle_finder_input(&key);
+      return;
+    }
+
+    self.status = None;
+
+    let control = self.modifiers.control_key();
+
+    let alt = self.modifiers.alt_key();
+
+    let shift = self.modifiers.shift_key();
+
+    let group = match &key {
+      Key::Named(NamedKey::Backspace) => Some(Group::Backspace),
+      Key::Named(NamedKey::Delete) => Some(Group::Delete),
+      Key::Named(NamedKey::Space) => Some(Group::Type),
+      Key::Character(_) if !control && !alt => Some(Group::Type),
+      _ => None,
+    };
+
+    let selections = self.buffer().selections();
+
+    let mut reveal_cursor = true;
+
+    match key {
+      Key::Named(NamedKey::ArrowUp) if control && alt => {
+        self.add_cursor_vertically(false);
+      }
+      Key::Named(NamedKey::ArrowDown) if control && alt => {
+        self.add_cursor_vertically(true);
+      }
+      Key::Named(NamedKey::ArrowUp) if control => {
+        self.scroll_by(-1);
+        reveal_cursor = false;
+      }
+      Key::Named(NamedKey::ArrowDown) if control => {
+        self.scroll_by(1);
+        reveal_cursor = false;
+      }
+      Key::Named(named)
+        if alt && let Some(direction) = Direction::from_key(&named) =>
+      {
+        if shift {
+          self.resize_pane(direction);
+        } else {
+          self.focus_neighbor(direction);
+        }
+      }
+      Key::Named(NamedKey::Backspace) if control => {
+        let separators = self.word_separators.clone();
+
+        self.delete(|buffer, cursor| {
+          buffer.previous_word_boundary(cursor, &separators)
+        });
+      }
+      Key::Named(NamedKey::Delete) if control => {
+        let separators = self.word_separators.clone();
+
+        self.delete(|buffer, cursor| {
+          buffer.next_word_boundary(cursor, &separators)
+        });
+      }
+      Key::Named(NamedKey::Backspace) => self.delete(|buffer, cursor| {
+        buffer.content.previous_grapheme_boundary(cursor)
+      }),
+      Key::Named(NamedKey::Delete) => self
+        .delete(|buffer, cursor| buffer.content.next_grapheme_boundary(cursor)),
+      Key::Named(NamedKey::ArrowLeft) if control => {
+        let separators = self.word_separators.clone();
+
+        self.move_cursors(shift, |buffer, selection| {
+          buffer.previous_word_boundary(selection.head, &separators)
+        });
+      }
+      Key::Named(NamedKey::ArrowRight) if control => {
+        let separators = self.word_separators.clone();
+
+        self.move_cursors(shift, |buffer, selection| {
+          buffer.next_word_boundary(selection.head, &separators)
+        });
+      }
+      // Without shift, collapse selections to their start or end.
+      Key::Named(NamedKey::ArrowLeft) => {
+        self.move_cursors(shift, |buffer, selection| {
+          if shift || selection.is_empty() {
+            buffer.content.previous_grapheme_boundary(selection.head)
+          } else {
+            selection.start()
+          }
+        });
+      }
+      Key::Named(NamedKey::ArrowRight) => {
+        self.move_cursors(shift, |buffer, selection| {
+          if shift || selection.is_empty() {
+            buffer.content.next_grapheme_boundary(selection.head)
+          } else {
+            selection.end()
+          }
+        });
+      }
+      Key::Named(NamedKey::ArrowUp) => self.move_lines(shift, -1),
+      Key::Named(NamedKey::ArrowDown) => self.move_lines(shift, 1),
+      Key::Named(NamedKey::Home) if control => {
+        self.move_cursors(shift, |_, _| 0);
+      }
+      Key::Named(NamedKey::End) if control => {
+        self.move_cursors(shift, |buffer, _| buffer.content.len_chars());
+      }
+      // Go to the first character that is not indentation, or from there to
+      // the start of the line.
+      Key::Named(NamedKey::Home) => {
+        self.move_cursors(shift, |buffer, selection| {
+          let line = buffer.content.char_to_line(selection.head);
+
+          let indent_end = buffer.line_indent_end(line);
+
+          if selection.head == indent_end {
+            buffer.content.line_to_char(line)
+          } else {
+            indent_end
+          }
+        });
+      }
+      Key::Named(NamedKey::End) => {
+        self.move_cursors(shift, |buffer, selection| {
+          let line = buffer.content.char_to_line(selection.head);
+          buffer.content.line_to_char(line) + buffer.line_len(line)
+        });
+      }
+      Key::Named(NamedKey::Escape) if !self.buffer().cursors.is_empty() => {
+        self.buffer_mut().cursors.clear();
+      }
+      Key::Named(NamedKey::Escape) => {
+        self.quit();
+      }
+      Key::Named(NamedKey::Enter) if self.pipe.is_some() && control => {
+        self.commit();
+      }
+      Key::Named(NamedKey::Enter) => {
+        self.replace_selections(self.buffer().line_ending.as_str());
+      }
+      Key::Named(NamedKey::PageDown) if control => self.next_buffer(),
+      Key::Named(NamedKey::PageUp) if control => self.previous_buffer(),
+      Key::Named(NamedKey::PageDown) => self.page(shift, true),
+      Key::Named(NamedKey::PageUp) => self.page(shift, false),
+      Key::Named(NamedKey::Tab) if control && shift => {
+        self.previous_buffer();
+      }
+      Key::Named(NamedKey::Tab) if control => self.next_buffer(),
+      Key::Named(NamedKey::Space) => self.replace_selections(" "),
+      Key::Character(c) if control || alt => {
+        self.handle_command(&c);
+      }
+      Key::Character(c) => self.replace_selections(&c),
+      _ => {}
+    }
+
+    let buffer = self.buffer_mut();
+
+    buffer.end_transaction(group);
+
+    if group.is_none() {
+      buffer.history.seal();
+    }
+
+    if self.buffer().selections() != selections {
+      self.update_primary();
+    }
+
+    if reveal_cursor {
+      self.scroll_to_cursor();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::app::tests::{command, key, type_text},
+  };
+
+  #[test]
+  fn ime_composition() {
+    let mut app = App::new();
+
+    type_text(&mut app, "ab");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::ArrowLeft);
+
+    app.handle_ime(Ime::Enabled);
+    app.handle_ime(Ime::Preedit("にほ".into(), Some((6, 6))));
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].lines, ["aにほb"]);
+    assert_eq!(view.panes[0].preedit, Some((0, 1..3)));
+    assert_eq!(view.panes[0].cursor, Some((0, 3)));
+
+    let (advance, line_height) = (app.metrics.advance, app.metrics.line_height);
+
+    assert_eq!(
+      app.cursor_area(&view),
+      Some((
+        PhysicalPosition::new(
+          Pane::PADDING + 3.0 * advance,
+          Tab::HEIGHT + Pane::PADDING
+        ),
+        PhysicalSize::new(advance, line_height),
+      ))
+    );
+
+    app.handle_ime(Ime::Preedit("にほ".into(), None));
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].cursor, None);
+    assert_eq!(
+      app.cursor_area(&view).map(|(position, _)| position.x),
+      Some(Pane::PADDING + advance)
+    );
+
+    app.handle_ime(Ime::Commit("日本".into()));
+
+    assert_eq!(app.buffer().content.to_string(), "a日本b");
+    assert_eq!(app.buffer().cursor, 3);
+    assert_eq!(app.view().panes[0].preedit, None);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "ab");
+
+    app.handle_ime(Ime::Preedit("に".into(), Some((3, 3))));
+    app.handle_ime(Ime::Disabled);
+
+    assert_eq!(app.view().panes[0].lines, ["ab"]);
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.buffer().content.to_string(), "axb");
+  }
+
+  #[test]
+  fn ime_commit_in_prompt_and_finder() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "o");
+
+    app.handle_ime(Ime::Preedit("にほ".into(), Some((6, 6))));
+
+    assert_eq!(app.preedit, None);
+
+    app.handle_ime(Ime::Commit("日本".into()));
+
+    assert_eq!(app.prompt.as_ref().unwrap().input, "日本");
+    assert_eq!(app.buffer().content.to_string(), "");
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Escape);
+
+    command(&mut app, ModifiersState::CONTROL, "p");
+
+    app.handle_ime(Ime::Preedit("にほ".into(), Some((6, 6))));
+
+    assert_eq!(app.preedit, None);
+
+    app.handle_ime(Ime::Commit("日本".into()));
+
+    assert_eq!(app.view().finder.unwrap().query, "日本");
+    assert_eq!(app.buffer().content.to_string(), "");
+  }
+
+  #[test]
+  fn altgr_character() {
+    let mut app = App::new();
+
+    let altgr = |app: &mut App, key: &str, unmodified: &str| {
+      app.modifiers = ModifiersState::CONTROL | ModifiersState::ALT;
+      app.handle_key_event(
+        Key::Character(key.into()),
+        &Key::Character(unmodified.into()),
+        ElementState::Pressed,
+      );
+      app.modifiers = ModifiersState::empty();
+    };
+
+    altgr(&mut app, "@", "q");
+
+    assert_eq!(app.buffer().content.to_string(), "@");
+
+    altgr(&mut app, "z", "z");
+
+    assert_eq!(app.buffer().content.to_string(), "");
+
+    command(&mut app, ModifiersState::CONTROL, "p");
+
+    altgr(&mut app, "@", "q");
+
+    assert_eq!(app.view().finder.unwrap().query, "@");
+  }
+}
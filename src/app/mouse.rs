@@ -0,0 +1,892 @@
+use super::*;
+
+/// How often dragging a selection past the edge of a pane scrolls it.
+const AUTO_SCROLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lines scrolled by one click of the mouse wheel.
+const WHEEL_LINES: f32 = 3.0;
+
+impl App {
+  /// Scroll pane `id` by `dx` and `dy` pixels without moving its cursor, as
+  /// far as the last line and the end of the longest line in view. Reaching
+  /// either stops its momentum.
+  pub(super) fn scroll_pixels(&mut self, id: u64, (dx, dy): (f32, f32)) {
+    let Some(rect) = self
+      .layout
+      .layout(self.text_area())
+      .into_iter()
+      .find(|(pane, _)| *pane == id)
+      .map(|(_, rect)| rect)
+    else {
+      return;
+    };
+
+    let (rows, columns) = (self.rows(rect), self.columns(rect));
+
+    let Metrics {
+      advance,
+      line_height,
+      ..
+    } = self.metrics;
+
+    let Some(pane) = self.panes.get_mut(&id) else {
+      return;
+    };
+
+    let Some(buffer) = self.buffers.iter().find(|b| b.id == pane.buffer) else {
+      return;
+    };
+
+    let lines = buffer.content.len_lines();
+
+    let longest = (pane.scroll..(pane.scroll + rows + 1).min(lines))
+      .map(|line| buffer.line_len(line))
+      .max()
+      .unwrap_or_default();
+
+    let (x, y) = (
+      pane.scroll_column as f32 * advance + pane.scroll_offset.0,
+      pane.scroll as f32 * line_height + pane.scroll_offset.1,
+    );
+
+    // Leave room after the longest line for the cursor, as when it follows
+    // the cursor there, and never jump back from further than that.
+    let max_x = ((longest + 1).saturating_sub(columns) as f32 * advance).max(x);
+
+    let max_y = (lines - 1) as f32 * line_height;
+
+    let (new_x, new_y) =
+      ((x + dx).clamp(0.0, max_x), (y + dy).clamp(0.0, max_y));
+
+    if new_x != x + dx || new_y != y + dy {
+      pane.momentum = Momentum::default();
+    }
+
+    pane.scroll_column = (new_x / advance) as usize;
+    pane.scroll = (new_y / line_height) as usize;
+    pane.scroll_offset = (
+      new_x - pane.scroll_column as f32 * advance,
+      new_y - pane.scroll as f32 * line_height,
+    );
+  }
+
+  /// Play out the momentum of every pane for `elapsed`.
+  pub(super) fn step_scroll(&mut self, elapsed: Duration) {
+    let ids = self.panes.keys().copied().collect::<Vec<u64>>();
+
+    for id in ids {
+      let Some(pane) = self.panes.get_mut(&id) else {
+        continue;
+      };
+
+      if !pane.momentum.is_moving() {
+        continue;
+      }
+
+      let delta = pane.momentum.step(elapsed);
+
+      self.scroll_pixels(id, delta);
+    }
+  }
+
+  /// Play out scroll momentum for the time since the last frame, up to a
+  /// limit so that a stalled frame does not jump.
+  pub(super) fn animate_scroll(&mut self) {
+    let elapsed = self.scroll_animated_at.elapsed();
+
+    self.scroll_animated_at = Instant::now();
+
+    self.step_scroll(elapsed.min(Duration::from_millis(100)));
+  }
+
+  /// Char index in the focused pane's buffer closest to `position`, found by
+  /// measuring the glyphs of the line there. Positions past the edges of the
+  /// pane give text scrolled out of view.
+  pub(super) fn char_at(&self, position: PhysicalPosition<f64>) -> usize {
+    let Some(rect) = self.pane_rect() else {
+      return self.buffer().cursor;
+    };
+
+    let buffer = self.buffer();
+
+    let content = &buffer.content;
+
+    let pane = &self.panes[&self.focus];
+
+    let row = ((position.y as f32 - rect.y - Pane::PADDING
+      + pane.scroll_offset.1)
+      / self.metrics.line_height)
+      .floor() as isize;
+
+    let line = pane
+      .scroll
+      .saturating_add_signed(row)
+      .min(content.len_lines() - 1);
+
+    let (start, len) = (content.line_to_char(line), buffer.line_len(line));
+
+    let x = position.x as f32 - rect.x - Pane::PADDING + pane.scroll_offset.0;
+
+    let column = if x < 0.0 {
+      pane
+        .scroll_column
+        .saturating_sub((-x / self.metrics.advance).ceil() as usize)
+    } else {
+      let visible = content
+        .slice(start + pane.scroll_column.min(len)..start + len)
+        .to_string();
+
+      pane.scroll_column + self.column_at(&visible, x)
+    };
+
+    content.snap_to_grapheme_boundary(start + column.min(len))
+  }
+
+  /// Char index of the grapheme boundary in `text` nearest to `x` pixels
+  /// from its start.
+  pub(super) fn column_at(&self, text: &str, x: f32) -> usize {
+    let mut left = 0.0;
+
+    let mut column = 0;
+
+    for grapheme in text.graphemes(true) {
+      let width = self.text_width(grapheme);
+
+      if x < left + width / 2.0 {
+        break;
+      }
+
+      left += width;
+      column += grapheme.chars().count();
+    }
+
+    column
+  }
+
+  /// Text selected by clicking `count` times at `char_idx`: nothing, the
+  /// word there, or its line along with the line ending.
+  pub(super) fn click_range(
+    &self,
+    char_idx: usize,
+    count: usize,
+  ) -> Range<usize> {
+    let buffer = self.buffer();
+
+    match count {
+      1 => char_idx..char_idx,
+      2 => buffer
+        .word_at(char_idx, &self.word_separators)
+        .unwrap_or(char_idx..char_idx),
+      _ => {
+        let line = buffer.content.char_to_line(char_idx);
+
+        buffer.content.line_to_char(line)..buffer.content.line_to_char(line + 1)
+      }
+    }
+  }
+
+  /// Place the cursor at `position`, or on a double or triple click, select
+  /// the word or line there, and start selecting by dragging.
+  pub(super) fn press(&mut self, position: PhysicalPosition<f64>) {
+    let count = match &self.click {
+      Some(click) if click.is_repeated_at(position) => click.count % 3 + 1,
+      _ => 1,
+    };
+
+    let origin = self.click_range(self.char_at(position), count);
+
+    let buffer = self.buffer_mut();
+
+    buffer.set_selections(&[Selection::new(origin.start, origin.end)], 0);
+    buffer.history.seal();
+
+    self.click = Some(Click {
+      count,
+      origin,
+      position,
+      time: Instant::now(),
+    });
+
+    self.dragging_selection = true;
+    self.scroll_to_cursor_with_margin(0);
+  }
+
+  /// Extend the selection made by the last click to `position`, by whole
+  /// words or lines after a double or triple click.
+  pub(super) fn drag_to(&mut self, position: PhysicalPosition<f64>) {
+    let Some(Click { count, origin, .. }) = self.click.clone() else {
+      return;
+    };
+
+    let range = self.click_range(self.char_at(position), count);
+
+    let selection = if range.start < origin.start {
+      Selection::new(origin.end, range.start)
+    } else {
+      Selection::new(origin.start, range.end.max(origin.end))
+    };
+
+    self.buffer_mut().set_selections(&[selection], 0);
+    self.scroll_to_cursor_with_margin(0);
+  }
+
+  /// While dragging a selection with the mouse held past the edge of the
+  /// pane, keep extending it, which scrolls further the further away the
+  /// mouse is.
+  pub(super) fn auto_scroll(&mut self) {
+    if !self.dragging_selection
+      || self.auto_scrolled_at.elapsed() < AUTO_SCROLL_INTERVAL
+    {
+      return;
+    }
+
+    if let Some(position) = self.mouse_position {
+      self.drag_to(position);
+    }
+
+    self.auto_scrolled_at = Instant::now();
+  }
+
+  /// Paste the primary selection at `position`, as when middle clicking.
+  pub(super) fn paste_primary(&mut self, position: PhysicalPosition<f64>) {
+    let text = match self.clipboard.get_primary() {
+      Ok(Some(text)) => text,
+      Ok(None) => return,
+      Err(error) => {
+        self.status = Some(error.summary());
+        return;
+      }
+    };
+
+    let char_idx = self.char_at(position);
+
+    self
+      .buffer_mut()
+      .set_selections(&[Selection::cursor(char_idx)], 0);
+
+    self.insert_pasted(&text);
+
+    let buffer = self.buffer_mut();
+
+    buffer.end_transaction(None);
+    buffer.history.seal();
+
+    self.scroll_to_cursor();
+  }
+
+  /// Index of the tab under `position`, if any.
+  pub(super) fn tab_at(
+    &self,
+    position: PhysicalPosition<f64>,
+  ) -> Option<usize> {
+    let count = self.buffers.len();
+
+    let window_width = self.size.width as f32;
+
+    let (x, y) = (position.x as f32, position.y as f32);
+
+    let tabs_width = Tab::width(count, window_width) * count as f32;
+
+    ((0.0..Tab::HEIGHT).contains(&y) && (0.0..tabs_width).contains(&x))
+      .then(|| Tab::index_at(x, count, window_width))
+  }
+
+  pub(super) fn handle_cursor_moved(
+    &mut self,
+    position: PhysicalPosition<f64>,
+  ) {
+    self.mouse_position = Some(position);
+
+    if self.dragging_selection {
+      self.drag_to(position);
+    }
+
+    if self.dragging_tab {
+      let index = Tab::index_at(
+        position.x as f32,
+        self.buffers.len(),
+        self.size.width as f32,
+      );
+
+      if index != self.active() {
+        self.move_buffer(index);
+      }
+    }
+  }
+
+  pub(super) fn handle_mouse_input(
+    &mut self,
+    state: ElementState,
+    button: MouseButton,
+  ) {
+    if state == ElementState::Released {
+      if button == MouseButton::Left {
+        if self.dragging_selection {
+          self.update_primary();
+        }
+
+        self.dragging_selection = false;
+        self.dragging_tab = false;
+      }
+
+      return;
+    }
+
+    if self.prompt.is_some() {
+      return;
+    }
+
+    let Some(position) = self.mouse_position else {
+      return;
+    };
+
+    let Some(index) = self.tab_at(position) else {
+      let Some((pane, _)) = self
+        .layout
+        .layout(self.text_area())
+        .into_iter()
+        .find(|(_, rect)| rect.contains(position.x as f32, position.y as f32))
+      else {
+        return;
+      };
+
+      match button {
+        MouseButton::Left => {
+          self.focus_pane(pane);
+
+          if self.modifiers.alt_key() {
+            self.add_cursor(self.char_at(position));
+          } else {
+            self.press(position);
+          }
+        }
+        MouseButton::Middle => {
+          self.focus_pane(pane);
+          self.paste_primary(position);
+        }
+        _ => {}
+      }
+
+      return;
+    };
+
+    match button {
+      MouseButton::Left => {
+        self.activate(index);
+        self.dragging_tab = true;
+      }
+      MouseButton::Middle => {
+        self.activate(index);
+        self.close_buffer();
+      }
+      _ => {}
+    }
+  }
+
+  /// Scroll the pane under the mouse, or else the focused pane, easing
+  /// through wheel clicks and following the touchpad directly. Shift turns
+  /// vertical scrolling horizontal.
+  pub(super) fn handle_mouse_wheel(
+    &mut self,
+    delta: MouseScrollDelta,
+    phase: TouchPhase,
+  ) {
+    let id = self
+      .mouse_position
+      .and_then(|position| {
+        self
+          .layout
+          .layout(self.text_area())
+          .into_iter()
+          .find(|(_, rect)| rect.contains(position.x as f32, position.y as f32))
+      })
+      .map_or(self.focus, |(id, _)| id);
+
+    let (x, y) = match delta {
+      MouseScrollDelta::LineDelta(x, y) => {
+        let distance = WHEEL_LINES * self.metrics.line_height;
+        (x * distance, y * distance)
+      }
+      MouseScrollDelta::PixelDelta(position) => {
+        (position.x as f32, position.y as f32)
+      }
+    };
+
+    // Positive deltas move the text right and down, which scrolls the view
+    // left and up.
+    let distance = if self.modifiers.shift_key() {
+      (-y, -x)
+    } else {
+      (-x, -y)
+    };
+
+    let Some(pane) = self.panes.get_mut(&id) else {
+      return;
+    };
+
+    if let MouseScrollDelta::LineDelta(..) = delta {
+      pane.momentum.wheel(distance);
+      return;
+    }
+
+    let now = Instant::now();
+
+    pane.momentum.touch(distance, now);
+
+    self.scroll_pixels(id, distance);
+
+    if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled)
+      && let Some(pane) = self.panes.get_mut(&id)
+    {
+      pane.momentum.lift(now);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::app::tests::{command, key, type_text},
+  };
+
+  fn click(app: &mut App, button: MouseButton, x: f64, y: f64) {
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+    app.handle_mouse_input(ElementState::Pressed, button);
+    app.handle_mouse_input(ElementState::Released, button);
+  }
+
+  #[test]
+  fn click_tab() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    click(&mut app, MouseButton::Left, 250.0, 10.0);
+
+    assert_eq!(app.active(), 1);
+
+    click(&mut app, MouseButton::Left, 10.0, 100.0);
+
+    assert_eq!(app.active(), 1);
+
+    click(&mut app, MouseButton::Left, 1000.0, 10.0);
+
+    assert_eq!(app.active(), 1);
+  }
+
+  #[test]
+  fn middle_click_closes_tab() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    click(&mut app, MouseButton::Middle, 10.0, 10.0);
+
+    assert_eq!(
+      app
+        .buffers
+        .iter()
+        .map(|buffer| buffer.id)
+        .collect::<Vec<u64>>(),
+      [1, 2]
+    );
+  }
+
+  #[test]
+  fn drag_tab() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "n");
+    command(&mut app, ModifiersState::CONTROL, "n");
+
+    app.handle_cursor_moved(PhysicalPosition { x: 10.0, y: 10.0 });
+    app.handle_mouse_input(ElementState::Pressed, MouseButton::Left);
+    app.handle_cursor_moved(PhysicalPosition { x: 300.0, y: 80.0 });
+
+    assert_eq!(app.active(), 1);
+
+    app.handle_cursor_moved(PhysicalPosition { x: 1500.0, y: 10.0 });
+    app.handle_mouse_input(ElementState::Released, MouseButton::Left);
+    app.handle_cursor_moved(PhysicalPosition { x: 10.0, y: 10.0 });
+
+    assert_eq!(app.active(), 2);
+    assert_eq!(app.buffer().id, 0);
+
+    assert_eq!(
+      app
+        .buffers
+        .iter()
+        .map(|buffer| buffer.id)
+        .collect::<Vec<u64>>(),
+      [1, 2, 0]
+    );
+  }
+
+  #[test]
+  fn click_focuses_pane() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::ALT, "v");
+
+    click(&mut app, MouseButton::Left, 100.0, 400.0);
+
+    assert_eq!(app.focus, 0);
+
+    click(&mut app, MouseButton::Left, 1000.0, 400.0);
+
+    assert_eq!(app.focus, 1);
+  }
+
+  #[test]
+  fn alt_click_adds_cursor() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abc\ndef");
+
+    app.modifiers = ModifiersState::ALT;
+
+    let (x, y) = (
+      Pane::PADDING + 2.0 * app.metrics.advance,
+      Tab::HEIGHT + Pane::PADDING + 1.5 * app.metrics.line_height,
+    );
+
+    click(&mut app, MouseButton::Left, x.into(), y.into());
+
+    app.modifiers = ModifiersState::empty();
+
+    assert_eq!(app.buffer().cursor, 6);
+    assert_eq!(app.buffer().cursors, [Selection::cursor(7)]);
+
+    type_text(&mut app, "-");
+
+    assert_eq!(app.buffer().content.to_string(), "abc\nde-f-");
+  }
+
+  #[test]
+  fn primary_selection() {
+    let mut app = App::new();
+
+    type_text(&mut app, "abc\ndef");
+
+    key(&mut app, ModifiersState::SHIFT, NamedKey::ArrowLeft);
+
+    assert_eq!(app.clipboard.get_primary().unwrap().as_deref(), Some("f"));
+
+    command(&mut app, ModifiersState::CONTROL, "a");
+
+    assert_eq!(
+      app.clipboard.get_primary().unwrap().as_deref(),
+      Some("abc\ndef")
+    );
+
+    let (x, y) = (
+      Pane::PADDING + app.metrics.advance,
+      Tab::HEIGHT + Pane::PADDING + 0.5 * app.metrics.line_height,
+    );
+
+    click(&mut app, MouseButton::Middle, x.into(), y.into());
+
+    assert_eq!(app.buffer().content.to_string(), "aabc\ndefbc\ndef");
+    assert_eq!(app.buffer().selection(), None);
+
+    command(&mut app, ModifiersState::CONTROL, "z");
+
+    assert_eq!(app.buffer().content.to_string(), "abc\ndef");
+  }
+
+  /// Window position `column` cells along `line` in a pane filling the window.
+  fn text_position(app: &App, line: f64, column: f64) -> (f64, f64) {
+    (
+      f64::from(Pane::PADDING) + column * f64::from(app.metrics.advance),
+      f64::from(Tab::HEIGHT + Pane::PADDING)
+        + (line + 0.5) * f64::from(app.metrics.line_height),
+    )
+  }
+
+  #[test]
+  fn click_places_cursor() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello\nworld\ne\u{301}e\u{301}");
+
+    let (x, y) = text_position(&app, 1.0, 2.4);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().cursor, 8);
+
+    let (x, y) = text_position(&app, 0.0, 50.0);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().cursor, 5);
+
+    let (x, y) = text_position(&app, 2.0, 0.9);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().cursor, 12);
+
+    let (x, y) = text_position(&app, 2.0, 1.2);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().cursor, 14);
+  }
+
+  #[test]
+  fn drag_selects() {
+    let mut app = App::new();
+
+    type_text(&mut app, "hello\nworld");
+
+    let (x, y) = text_position(&app, 0.0, 1.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+    app.handle_mouse_input(ElementState::Pressed, MouseButton::Left);
+
+    let (x, y) = text_position(&app, 1.0, 3.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+    app.handle_mouse_input(ElementState::Released, MouseButton::Left);
+
+    assert_eq!(app.buffer().selection(), Some(1..9));
+    assert_eq!(app.buffer().cursor, 9);
+    assert_eq!(
+      app.clipboard.get_primary().unwrap().as_deref(),
+      Some("ello\nwor")
+    );
+
+    let (x, y) = text_position(&app, 0.0, 0.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+
+    assert_eq!(app.buffer().selection(), Some(1..9));
+  }
+
+  #[test]
+  fn double_and_triple_click() {
+    let mut app = App::new();
+
+    type_text(&mut app, "foo bar\nbaz");
+
+    let (x, y) = text_position(&app, 0.0, 5.2);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().cursor, 5);
+    assert_eq!(app.buffer().selection(), None);
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().selection(), Some(4..7));
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().selection(), Some(0..8));
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    assert_eq!(app.buffer().selection(), None);
+
+    app.click = None;
+
+    click(&mut app, MouseButton::Left, x, y);
+
+    app.handle_mouse_input(ElementState::Pressed, MouseButton::Left);
+
+    let (x, y) = text_position(&app, 1.0, 1.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+
+    assert_eq!(app.buffer().selection(), Some(4..11));
+
+    let (x, y) = text_position(&app, 0.0, 1.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+
+    assert_eq!(app.buffer().selection(), Some(0..7));
+    assert_eq!(app.buffer().cursor, 0);
+  }
+
+  #[test]
+  fn drag_auto_scrolls() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 600);
+
+    for _ in 0..99 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    let (x, y) = text_position(&app, 0.0, 0.0);
+
+    app.handle_cursor_moved(PhysicalPosition { x, y });
+    app.handle_mouse_input(ElementState::Pressed, MouseButton::Left);
+
+    let bottom = f64::from(app.text_area().bottom());
+
+    app.handle_cursor_moved(PhysicalPosition {
+      x,
+      y: bottom + 10.0,
+    });
+
+    let scroll = app.panes[&0].scroll;
+
+    assert!(scroll > 0);
+    assert_eq!(
+      app.buffer().selection(),
+      Some(0..scroll + app.rows(app.text_area()) - 1)
+    );
+
+    app.auto_scrolled_at = Instant::now() - AUTO_SCROLL_INTERVAL;
+
+    app.auto_scroll();
+
+    assert!(app.panes[&0].scroll > scroll);
+
+    app.handle_mouse_input(ElementState::Released, MouseButton::Left);
+
+    let scroll = app.panes[&0].scroll;
+
+    app.auto_scrolled_at = Instant::now() - AUTO_SCROLL_INTERVAL;
+
+    app.auto_scroll();
+
+    assert_eq!(app.panes[&0].scroll, scroll);
+  }
+
+  #[test]
+  fn wheel_scrolls_smoothly() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 600);
+
+    let rows = app.rows(app.text_area());
+
+    for _ in 0..99 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    app.handle_mouse_wheel(
+      MouseScrollDelta::LineDelta(0.0, -1.0),
+      TouchPhase::Moved,
+    );
+
+    assert_eq!(app.panes[&0].scroll, 0);
+
+    app.step_scroll(Duration::from_millis(16));
+
+    let (x, y) = app.panes[&0].scroll_offset;
+
+    assert_eq!((app.panes[&0].scroll, x), (0, 0.0));
+    assert!(y > 0.0);
+
+    let view = app.view();
+
+    assert_eq!(view.panes[0].scroll_offset, (0.0, y));
+    assert_eq!(view.panes[0].lines.len(), rows + 1);
+
+    for _ in 0..100 {
+      app.step_scroll(Duration::from_millis(16));
+    }
+
+    assert_eq!(app.panes[&0].scroll, 3);
+    assert_eq!(app.panes[&0].scroll_offset, (0.0, 0.0));
+    assert_eq!(app.buffer().cursor, 0);
+  }
+
+  #[test]
+  fn touchpad_scrolls_by_pixels() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 600);
+
+    for _ in 0..99 {
+      key(&mut app, ModifiersState::empty(), NamedKey::Enter);
+    }
+
+    key(&mut app, ModifiersState::CONTROL, NamedKey::Home);
+
+    let line_height = app.metrics.line_height;
+
+    app.handle_mouse_wheel(
+      MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+        0.0,
+        f64::from(-1.25 * line_height),
+      )),
+      TouchPhase::Moved,
+    );
+
+    assert_eq!(app.panes[&0].scroll, 1);
+    assert_eq!(app.panes[&0].scroll_offset, (0.0, 0.25 * line_height));
+
+    app.handle_mouse_wheel(
+      MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 100.0)),
+      TouchPhase::Moved,
+    );
+
+    assert_eq!(app.panes[&0].scroll, 0);
+    assert_eq!(app.panes[&0].scroll_offset, (0.0, 0.0));
+
+    app.handle_mouse_wheel(
+      MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, -20.0)),
+      TouchPhase::Moved,
+    );
+    app.handle_mouse_wheel(
+      MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 0.0)),
+      TouchPhase::Ended,
+    );
+
+    let (scroll, (_, offset)) =
+      (app.panes[&0].scroll, app.panes[&0].scroll_offset);
+
+    app.step_scroll(Duration::from_millis(16));
+
+    assert!(
+      app.panes[&0].scroll as f32 * line_height + app.panes[&0].scroll_offset.1
+        > scroll as f32 * line_height + offset
+    );
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.panes[&0].scroll, 0);
+    assert_eq!(app.panes[&0].scroll_offset, (0.0, 0.0));
+    assert!(!app.panes[&0].momentum.is_moving());
+  }
+
+  #[test]
+  fn shift_wheel_scrolls_horizontally() {
+    let mut app = App::new();
+
+    app.size = PhysicalSize::new(800, 600);
+
+    type_text(&mut app, &"x".repeat(200));
+
+    key(&mut app, ModifiersState::empty(), NamedKey::Home);
+
+    app.modifiers = ModifiersState::SHIFT;
+
+    app.handle_mouse_wheel(
+      MouseScrollDelta::LineDelta(0.0, -1.0),
+      TouchPhase::Moved,
+    );
+
+    for _ in 0..100 {
+      app.step_scroll(Duration::from_millis(16));
+    }
+
+    let columns = WHEEL_LINES * app.metrics.line_height / app.metrics.advance;
+
+    assert_eq!(app.panes[&0].scroll, 0);
+    assert_eq!(app.panes[&0].scroll_column, columns as usize);
+    assert_eq!(app.panes[&0].scroll_offset, (0.0, 0.0));
+  }
+}
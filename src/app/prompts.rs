@@ -0,0 +1,239 @@
+use super::*;
+
+impl App {
+  /// Open the quick open overlay over the current directory, and start
+  /// walking it for files.
+  pub(super) fn open_finder(&mut self) {
+    let root = match env::current_dir() {
+      Ok(root) => root,
+      Err(error) => {
+        self.status = Some(format!("failed to get current directory: {error}"));
+        return;
+      }
+    };
+
+    self.finder_generation += 1;
+
+    let finder = Finder::new(root, self.finder_generation);
+
+    if let Some(proxy) = self.proxy.clone() {
+      finder.spawn(move |event| {
+        proxy.send_event(event).ok();
+      });
+    }
+
+    self.finder = Some(finder);
+  }
+
+  pub(super) fn handle_finder_input(&mut self, key: &Key) {
+    let Some(finder) = &mut self.finder else {
+      return;
+    };
+
+    if self.modifiers.control_key() && matches!(key, Key::Character(_)) {
+      return;
+    }
+
+    match finder.handle_key(key) {
+      FinderAction::Cancel => self.finder = None,
+      FinderAction::Open(path) => {
+        self.finder = None;
+
+        if let Err(error) = self.open_file(path) {
+          self.status = Some(error.summary());
+        }
+      }
+      FinderAction::Pending => {}
+    }
+  }
+
+  pub(super) fn handle_prompt_input(&mut self, key: &Key) {
+    if self.diff.is_some()
+      && let Key::Named(named) = key
+    {
+      let page = self.pane_rect().map_or(1, |rect| self.rows(rect)) as isize;
+
+      let lines = match named {
+        NamedKey::ArrowDown => Some(1),
+        NamedKey::ArrowUp => Some(-1),
+        NamedKey::PageDown => Some(page),
+        NamedKey::PageUp => Some(-page),
+        _ => None,
+      };
+
+      if let Some(lines) = lines {
+        self.scroll_diff(lines);
+        return;
+      }
+    }
+
+    let Some(prompt) = &mut self.prompt else {
+      return;
+    };
+
+    if self.modifiers.control_key()
+      && let Key::Character(c) = key
+    {
+      if c == "v" {
+        match self.clipboard.get() {
+          Ok(Some(text)) => prompt.paste(&text),
+          Ok(None) => {}
+          Err(error) => self.status = Some(error.summary()),
+        }
+      }
+
+      return;
+    }
+
+    match prompt.handle_key(key) {
+      PromptAction::Cancel => {
+        let kind = prompt.kind.clone();
+
+        self.prompt = None;
+        self.cancel_pending();
+
+        match kind {
+          PromptKind::FileChanged(_) => self.file_changed("k"),
+          PromptKind::Recover(_) => self.recover("k"),
+          _ => {}
+        }
+      }
+      PromptAction::Pending => {}
+      PromptAction::Submit(input) => {
+        let kind = prompt.kind.clone();
+
+        self.prompt = None;
+
+        match kind {
+          PromptKind::Encoding => self.set_encoding(&input),
+          PromptKind::FileChanged(_) => self.file_changed(&input),
+          PromptKind::LineEnding => match input.as_str() {
+            "c" => self.set_line_ending(LineEnding::Crlf),
+            "l" => self.set_line_ending(LineEnding::Lf),
+            "r" => self.set_line_ending(LineEnding::Cr),
+            _ => {}
+          },
+          PromptKind::GoBack => self.go_back(&input),
+          PromptKind::Open => {
+            if !input.trim().is_empty()
+              && let Err(error) = self.open_file(PathBuf::from(input.trim()))
+            {
+              self.status = Some(error.summary());
+            }
+          }
+          PromptKind::Recover(_) => self.recover(&input),
+          PromptKind::SaveAs => {
+            if input.trim().is_empty() {
+              self.cancel_pending();
+            } else {
+              self.write(PathBuf::from(input));
+            }
+          }
+          PromptKind::SwitchBuffer => self.switch_buffer(&input),
+          PromptKind::UnsavedChanges => match input.as_str() {
+            "s" => self.save(),
+            "d" => match self.after_save.take() {
+              Some(AfterSave::Close) => self.remove_buffer(),
+              Some(AfterSave::Quit) => {
+                self.remove_buffer();
+                self.quit();
+              }
+              None => {}
+            },
+            _ => self.cancel_pending(),
+          },
+        }
+      }
+    }
+
+    self.check_file_changed();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::app::tests::{command, type_text},
+  };
+
+  #[test]
+  fn quick_open() {
+    let mut app = App::new();
+
+    command(&mut app, ModifiersState::CONTROL, "p");
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Escape),
+      ElementState::Pressed,
+    );
+
+    assert!(app.finder.is_none());
+
+    command(&mut app, ModifiersState::CONTROL, "p");
+
+    assert_eq!(app.finder_generation, 2);
+
+    app.handle_user_event(UserEvent::FinderPaths {
+      generation: 1,
+      paths: vec!["src/main.rs".into()],
+    });
+
+    app.handle_user_event(UserEvent::FinderPaths {
+      generation: 2,
+      paths: vec!["Cargo.toml".into(), "src/app.rs".into()],
+    });
+
+    app.handle_user_event(UserEvent::FinderDone { generation: 2 });
+
+    for c in ["t", "o", "m"] {
+      app
+        .handle_keyboard_input(Key::Character(c.into()), ElementState::Pressed);
+    }
+
+    app.finder.as_mut().unwrap().score();
+
+    assert_eq!(
+      app.view().finder,
+      Some(FinderView {
+        count: "1/2".into(),
+        matches: vec!["Cargo.toml".into()],
+        query: "tom".into(),
+        selected: 0,
+      })
+    );
+
+    app.handle_keyboard_input(
+      Key::Named(NamedKey::Enter),
+      ElementState::Pressed,
+    );
+
+    assert!(app.finder.is_none());
+    assert_eq!(
+      app.buffer().path,
+      Some(env::current_dir().unwrap().join("Cargo.toml"))
+    );
+
+    command(&mut app, ModifiersState::CONTROL, "p");
+
+    assert_eq!(app.view().finder.unwrap().query, "");
+  }
+
+  #[test]
+  fn prompt_ignores_commands_and_pastes() {
+    let mut app = App::new();
+
+    app.clipboard.set("foo.txt\nbar.txt".into()).unwrap();
+
+    command(&mut app, ModifiersState::CONTROL, "o");
+
+    command(&mut app, ModifiersState::CONTROL, "s");
+    command(&mut app, ModifiersState::CONTROL, "v");
+
+    assert_eq!(app.prompt.as_ref().unwrap().input, "foo.txt");
+
+    type_text(&mut app, "x");
+
+    assert_eq!(app.prompt.as_ref().unwrap().input, "foo.txtx");
+  }
+}
@@ -0,0 +1,20 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Arguments {
+  #[arg(help = "Files to open, created on first save if they do not exist")]
+  pub paths: Vec<PathBuf>,
+  #[arg(
+    long,
+    default_value_t = Pane::SCROLL_MARGIN,
+    help = "Lines and columns to keep visible around the cursor"
+  )]
+  pub scroll_margin: usize,
+  #[arg(
+    long,
+    default_value = WORD_SEPARATORS,
+    help = "Characters that separate words, along with whitespace"
+  )]
+  pub word_separators: String,
+}
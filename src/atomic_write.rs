@@ -0,0 +1,82 @@
+use super::*;
+
+/// Write `contents` to `path` without ever leaving a partially written file
+/// behind.
+///
+/// The contents are written to a temporary file in the same directory,
+/// flushed to disk, and then renamed over `path`, which is atomic on every
+/// platform we support. Permissions of an existing target are preserved.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> Result {
+  let directory = match path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new("."),
+  };
+
+  let mut file =
+    NamedTempFile::new_in(directory).context(error::CreateTempFile { path })?;
+
+  file
+    .write_all(contents)
+    .and_then(|()| file.as_file().sync_all())
+    .context(error::WriteFile { path })?;
+
+  if let Ok(metadata) = fs::metadata(path) {
+    fs::set_permissions(file.path(), metadata.permissions())
+      .context(error::WriteFile { path })?;
+  }
+
+  file
+    .persist(path)
+    .map_err(|error| error.error)
+    .context(error::RenameFile { path })?;
+
+  #[cfg(unix)]
+  File::open(directory)
+    .and_then(|directory| directory.sync_all())
+    .context(error::WriteFile { path })?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  #[test]
+  fn creates_file() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    atomic_write(&path, b"foo").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+  }
+
+  #[test]
+  fn replaces_file() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    fs::write(&path, "a much longer original file").unwrap();
+
+    atomic_write(&path, b"bar").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+
+    assert_eq!(fs::read_dir(tempdir.path()).unwrap().count(), 1);
+  }
+
+  #[test]
+  fn missing_directory() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("missing/foo.txt");
+
+    assert!(matches!(
+      atomic_write(&path, b"foo"),
+      Err(Error::CreateTempFile { .. })
+    ));
+  }
+}
@@ -0,0 +1,488 @@
+use super::*;
+
+/// An open document, along with what is needed to save it back to where it
+/// came from.
+pub struct Buffer {
+  /// Where the selection was started, if there is one. The selection runs
+  /// from here to the cursor.
+  pub anchor: Option<usize>,
+  pub content: Rope,
+  pub cursor: usize,
+  /// Cursors other than the main one, which edits apply to as well.
+  pub cursors: Vec<Selection>,
+  pub disk_stamp: Option<Stamp>,
+  pub encoding: Encoding,
+  pub file_changed: bool,
+  /// The main cursor's `Selection::goal`.
+  pub goal: Option<usize>,
+  pub history: History,
+  pub id: u64,
+  pub line_ending: LineEnding,
+  pub path: Option<PathBuf>,
+  pub revision: u64,
+  pub saved_revision: u64,
+  pub scratch: Option<Scratch>,
+  pub scratch_cursor: usize,
+  pub stdin: bool,
+  pub swap_revision: Option<u64>,
+}
+
+impl Buffer {
+  /// An empty untitled buffer. `id` must be unique within the session, and
+  /// names the buffer's swap file.
+  pub fn new(id: u64) -> Self {
+    Self {
+      anchor: None,
+      content: Rope::new(),
+      cursor: 0,
+      cursors: Vec::new(),
+      disk_stamp: None,
+      encoding: Encoding::default(),
+      file_changed: false,
+      goal: None,
+      history: History::default(),
+      id,
+      line_ending: LineEnding::default(),
+      path: None,
+      revision: 0,
+      saved_revision: 0,
+      scratch: None,
+      scratch_cursor: 0,
+      stdin: false,
+      swap_revision: None,
+    }
+  }
+
+  pub fn with_content(id: u64, content: Rope, encoding: Encoding) -> Self {
+    Self {
+      encoding,
+      line_ending: LineEnding::detect(&content).unwrap_or_default(),
+      content,
+      ..Self::new(id)
+    }
+  }
+
+  pub fn scratch(id: u64, scratch: Scratch) -> Result<Self> {
+    let (content, cursor) = scratch.load()?;
+
+    Ok(Self {
+      cursor,
+      scratch: Some(scratch),
+      scratch_cursor: cursor,
+      ..Self::with_content(id, content, Encoding::default())
+    })
+  }
+
+  /// Read and decode the file at `path`, or return `None` if it does not
+  /// exist.
+  pub fn read_file(path: &Path) -> Result<Option<(Encoding, Decoded)>> {
+    let mut bytes = Vec::new();
+
+    match File::open(path) {
+      Ok(mut file) => {
+        file
+          .read_to_end(&mut bytes)
+          .context(error::ReadFile { path })?;
+      }
+      Err(error) if error.kind() == io::ErrorKind::NotFound => {
+        return Ok(None);
+      }
+      Err(error) => {
+        return Err(error).context(error::OpenFile { path });
+      }
+    }
+
+    let encoding = Encoding::detect(&bytes);
+
+    Ok(Some((encoding, encoding.decode(&bytes))))
+  }
+
+  /// Replace text according to `edit`, without recording it in the
+  /// history.
+  fn apply(&mut self, edit: &Edit) {
+    self
+      .content
+      .remove(edit.position..edit.position + edit.removed.chars().count());
+    self.content.insert(edit.position, &edit.inserted);
+    self.revision += 1;
+  }
+
+  /// Visual column of `char_idx`, which is the number of grapheme clusters
+  /// before it on its line.
+  pub fn column(&self, char_idx: usize) -> usize {
+    let mut position = self
+      .content
+      .line_to_char(self.content.char_to_line(char_idx));
+
+    let mut column = 0;
+
+    while position < char_idx {
+      position = self.content.next_grapheme_boundary(position);
+      column += 1;
+    }
+
+    column
+  }
+
+  /// Char index of visual `column` of `line`, or of the end of the line if it
+  /// is too short to reach it.
+  pub fn column_position(&self, line: usize, column: usize) -> usize {
+    let mut position = self.content.line_to_char(line);
+
+    let end = position + self.line_len(line);
+
+    for _ in 0..column {
+      if position >= end {
+        break;
+      }
+
+      position = self.content.next_grapheme_boundary(position);
+    }
+
+    position
+  }
+
+  /// Finish the edits made since the last call as one undo step, merged into
+  /// the previous step if they continue the same `group`.
+  pub fn end_transaction(&mut self, group: Option<Group>) {
+    self.history.end_transaction(self.selections(), group);
+  }
+
+  /// Char ranges of the occurrences of `needle`, leaving out those that
+  /// start or end within a grapheme cluster.
+  pub fn find_all(&self, needle: &str) -> Vec<Range<usize>> {
+    if needle.is_empty() {
+      return Vec::new();
+    }
+
+    let text = self.content.to_string();
+
+    let len = needle.chars().count();
+
+    text
+      .match_indices(needle)
+      .map(|(byte_idx, _)| self.content.byte_to_char(byte_idx))
+      .map(|start| start..start + len)
+      .filter(|range| {
+        self.content.is_grapheme_boundary(range.start)
+          && self.content.is_grapheme_boundary(range.end)
+      })
+      .collect()
+  }
+
+  pub fn insert(&mut self, char_idx: usize, text: &str) {
+    self.record(Edit {
+      inserted: text.into(),
+      position: char_idx,
+      removed: String::new(),
+    });
+  }
+
+  /// Whether this is an untitled buffer that has never been edited, which
+  /// can be replaced by the next buffer opened.
+  pub fn is_pristine(&self) -> bool {
+    self.path.is_none()
+      && self.scratch.is_none()
+      && !self.stdin
+      && self.revision == 0
+  }
+
+  /// Whether to mark the buffer as having unsaved changes. Scratch buffers
+  /// are saved automatically, and stdin is written out on exit.
+  pub fn is_dirty(&self) -> bool {
+    self.is_modified() && self.scratch.is_none() && !self.stdin
+  }
+
+  pub fn is_modified(&self) -> bool {
+    self.revision != self.saved_revision
+  }
+
+  /// Length of `line` in chars, excluding its line ending.
+  pub fn line_len(&self, line: usize) -> usize {
+    let line = self.content.line(line);
+
+    let mut len = line.len_chars();
+
+    while len > 0 && matches!(line.char(len - 1), '\n' | '\r') {
+      len -= 1;
+    }
+
+    len
+  }
+
+  /// Char index of the first character on `line` that is not whitespace, or
+  /// of its end if there is none.
+  pub fn line_indent_end(&self, line: usize) -> usize {
+    let start = self.content.line_to_char(line);
+
+    start
+      + self
+        .content
+        .line(line)
+        .chars()
+        .take(self.line_len(line))
+        .take_while(|c| c.is_whitespace())
+        .count()
+  }
+
+  /// Record that the buffer's content has been saved, or loaded from where
+  /// it is saved.
+  pub fn mark_saved(&mut self) {
+    self.saved_revision = self.revision;
+    self.history.mark_saved();
+  }
+
+  /// Name shown to the user and matched when switching buffers.
+  pub fn name(&self) -> String {
+    if self.stdin {
+      return "stdin".into();
+    }
+
+    if self.scratch.is_some() {
+      return "scratch".into();
+    }
+
+    match &self.path {
+      Some(path) => path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned(),
+      None => format!("untitled-{}", self.id),
+    }
+  }
+
+  /// Whether the scratch file is behind the buffer's content or cursor.
+  pub fn needs_scratch_save(&self) -> bool {
+    self.scratch.is_some()
+      && (self.is_modified() || self.scratch_cursor != self.cursor)
+  }
+
+  /// Replace the content with that of the file on disk, keeping the cursor
+  /// on the same line and column where possible. Returns whether invalid
+  /// bytes were replaced while decoding.
+  pub fn reload(&mut self) -> Result<bool> {
+    let Some(path) = &self.path else {
+      return Ok(false);
+    };
+
+    let disk_stamp = Stamp::of(path);
+
+    let (encoding, decoded) = Self::read_file(path)?.unwrap_or_default();
+
+    let line = self.content.char_to_line(self.cursor);
+
+    let column = self.cursor - self.content.line_to_char(line);
+
+    // Replace the whole content as one edit, so that reloading can be undone.
+    self.record(Edit {
+      inserted: decoded.text,
+      position: 0,
+      removed: self.content.to_string(),
+    });
+
+    self.encoding = encoding;
+    self.line_ending = LineEnding::detect(&self.content).unwrap_or_default();
+
+    let line = line.min(self.content.len_lines() - 1);
+
+    self.anchor = None;
+    self.cursors.clear();
+    self.goal = None;
+
+    self.cursor = self.content.snap_to_grapheme_boundary(
+      self.content.line_to_char(line) + column.min(self.line_len(line)),
+    );
+
+    self.end_transaction(None);
+    self.mark_saved();
+    self.disk_stamp = disk_stamp;
+
+    Ok(decoded.lossy)
+  }
+
+  /// Apply `edit`, and add it to the pending transaction.
+  fn record(&mut self, edit: Edit) {
+    self.apply(&edit);
+
+    // Only the first edit of a transaction needs the selections, and making
+    // an edit at each of many cursors would otherwise copy them all each
+    // time.
+    let before = if self.history.is_pending() {
+      Vec::new()
+    } else {
+      self.selections()
+    };
+
+    self.history.record(edit, before);
+  }
+
+  pub fn remove(&mut self, char_range: Range<usize>) {
+    self.record(Edit {
+      inserted: String::new(),
+      position: char_range.start,
+      removed: self.content.slice(char_range).to_string(),
+    });
+  }
+
+  /// Write the undo history next to the file, to be loaded with it next
+  /// time.
+  pub fn save_history(&self) -> Result {
+    match &self.path {
+      Some(path) => self.history.save(path, &self.content.to_string()),
+      None => Ok(()),
+    }
+  }
+
+  pub fn save_scratch(&mut self) -> Result {
+    if let Some(scratch) = &self.scratch {
+      scratch.save(&self.content, self.cursor)?;
+      self.mark_saved();
+      self.scratch_cursor = self.cursor;
+    }
+
+    Ok(())
+  }
+
+  /// Convert every line ending in the buffer to `line_ending`, which is also
+  /// used for new lines from then on.
+  pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+    let (content, cursor) = line_ending.convert(&self.content, self.cursor);
+
+    if content != self.content {
+      self.record(Edit {
+        inserted: content.to_string(),
+        position: 0,
+        removed: self.content.to_string(),
+      });
+    }
+
+    self.anchor = None;
+    self.cursor = cursor;
+    self.cursors.clear();
+    self.goal = None;
+    self.line_ending = line_ending;
+    self.end_transaction(None);
+  }
+
+  /// The selected range, if any text is selected.
+  pub fn selection(&self) -> Option<Range<usize>> {
+    let anchor = self.anchor.filter(|&anchor| anchor != self.cursor)?;
+    Some(anchor.min(self.cursor)..anchor.max(self.cursor))
+  }
+
+  /// The main selection, followed by those of the other cursors.
+  pub fn selections(&self) -> Vec<Selection> {
+    iter::once(Selection {
+      anchor: self.anchor.unwrap_or(self.cursor),
+      goal: self.goal,
+      head: self.cursor,
+    })
+    .chain(self.cursors.iter().copied())
+    .collect()
+  }
+
+  /// Replace every cursor, merging those that overlap, with
+  /// `selections[main]` as the main one.
+  pub fn set_selections(&mut self, selections: &[Selection], main: usize) {
+    let (mut selections, main) = Selection::merge(selections, main);
+
+    let selection = selections.remove(main);
+
+    self.anchor = (!selection.is_empty()).then_some(selection.anchor);
+    self.cursor = selection.head;
+    self.cursors = selections;
+    self.goal = selection.goal;
+  }
+
+  pub fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      content: self.content.clone(),
+      path: self.path.clone(),
+    }
+  }
+
+  /// Move to another state in the history, as chosen by `travel`, and
+  /// return the edits applied to get there. If that is the state last saved,
+  /// the buffer is no longer modified.
+  pub fn travel(
+    &mut self,
+    travel: impl FnOnce(&mut History) -> Option<Travel>,
+  ) -> Option<Vec<Edit>> {
+    let Travel { edits, selections } = travel(&mut self.history)?;
+
+    for edit in &edits {
+      self.apply(edit);
+    }
+
+    self.set_selections(&selections, 0);
+
+    if self.history.is_saved() {
+      self.saved_revision = self.revision;
+    }
+
+    Some(edits)
+  }
+
+  /// Char ranges of the words on `line`, as found by `word_ranges`.
+  fn line_words(
+    &self,
+    line: usize,
+    separators: &str,
+  ) -> impl DoubleEndedIterator<Item = Range<usize>> {
+    let start = self.content.line_to_char(line);
+
+    word_ranges(&self.content.line(line).to_string(), separators)
+      .into_iter()
+      .map(move |range| start + range.start..start + range.end)
+  }
+
+  /// Char index of the end of the first word that ends after `char_idx`, or
+  /// the end of the buffer.
+  pub fn next_word_boundary(&self, char_idx: usize, separators: &str) -> usize {
+    (self.content.char_to_line(char_idx)..self.content.len_lines())
+      .find_map(|line| {
+        self
+          .line_words(line, separators)
+          .find(|range| range.end > char_idx)
+      })
+      .map_or(self.content.len_chars(), |range| range.end)
+  }
+
+  /// Char index of the start of the last word that starts before
+  /// `char_idx`, or the start of the buffer.
+  pub fn previous_word_boundary(
+    &self,
+    char_idx: usize,
+    separators: &str,
+  ) -> usize {
+    (0..=self.content.char_to_line(char_idx))
+      .rev()
+      .find_map(|line| {
+        self
+          .line_words(line, separators)
+          .rev()
+          .find(|range| range.start < char_idx)
+      })
+      .map_or(0, |range| range.start)
+  }
+
+  /// Range of the word containing or ending at `char_idx`, if there is one.
+  /// Punctuation does not count as a word.
+  pub fn word_at(
+    &self,
+    char_idx: usize,
+    separators: &str,
+  ) -> Option<Range<usize>> {
+    self
+      .line_words(self.content.char_to_line(char_idx), separators)
+      .find(|range| {
+        (range.start..=range.end).contains(&char_idx)
+          && self
+            .content
+            .slice(range.clone())
+            .chars()
+            .any(char::is_alphanumeric)
+      })
+  }
+}
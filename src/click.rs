@@ -0,0 +1,30 @@
+use super::*;
+
+/// A press of the left mouse button in a pane, kept to count repeated clicks
+/// and to extend the selection while dragging.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Click {
+  /// Number of clicks in quick succession, from one to three.
+  pub count: usize,
+  /// Text selected by the click, which dragging extends.
+  pub origin: Range<usize>,
+  pub position: PhysicalPosition<f64>,
+  pub time: Instant,
+}
+
+impl Click {
+  /// Longest time between clicks that count as a double or triple click.
+  const INTERVAL: Duration = Duration::from_millis(500);
+
+  /// Furthest the mouse may move between clicks that count as a double or
+  /// triple click, in pixels.
+  const SLOP: f64 = 4.0;
+
+  /// Whether a click at `position` now is the next of a double or triple
+  /// click with this one.
+  pub fn is_repeated_at(&self, position: PhysicalPosition<f64>) -> bool {
+    self.time.elapsed() <= Self::INTERVAL
+      && (self.position.x - position.x).abs() <= Self::SLOP
+      && (self.position.y - position.y).abs() <= Self::SLOP
+  }
+}
@@ -0,0 +1,20 @@
+use super::*;
+
+/// Where copied text goes, and pasted text comes from.
+pub trait Clipboard {
+  /// Text on the clipboard, or `None` if it holds no text.
+  fn get(&mut self) -> Result<Option<String>>;
+
+  fn set(&mut self, text: String) -> Result;
+
+  /// Text in the primary selection, which on X11 holds the text most recently
+  /// selected, and is pasted with the middle mouse button. Platforms without
+  /// one have no text in it.
+  fn get_primary(&mut self) -> Result<Option<String>> {
+    Ok(None)
+  }
+
+  fn set_primary(&mut self, _text: String) -> Result {
+    Ok(())
+  }
+}
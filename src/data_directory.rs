@@ -0,0 +1,11 @@
+use super::*;
+
+/// Per-user directory for state that outlives a single session, such as swap
+/// files, e.g. `~/.local/share/scratchpad` on Linux.
+pub fn data_directory() -> Result<PathBuf> {
+  Ok(
+    dirs::data_local_dir()
+      .context(error::DataDirectory)?
+      .join(env!("CARGO_PKG_NAME")),
+  )
+}
@@ -0,0 +1,21 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+  Down,
+  Left,
+  Right,
+  Up,
+}
+
+impl Direction {
+  pub fn from_key(key: &NamedKey) -> Option<Self> {
+    match key {
+      NamedKey::ArrowDown => Some(Self::Down),
+      NamedKey::ArrowLeft => Some(Self::Left),
+      NamedKey::ArrowRight => Some(Self::Right),
+      NamedKey::ArrowUp => Some(Self::Up),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,366 @@
+use super::*;
+
+/// Text decoded from a file, and whether any invalid byte sequences had to be
+/// replaced with U+FFFD in the process.
+#[derive(Debug, Default, PartialEq)]
+pub struct Decoded {
+  pub lossy: bool,
+  pub text: String,
+}
+
+/// Character encoding of a file on disk. Buffers are always UTF-8 in memory,
+/// and are transcoded when loaded and saved.
+///
+/// UTF-16 files are always written with a byte order mark.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+  Latin1,
+  #[default]
+  Utf8,
+  Utf8Bom,
+  Utf16Be,
+  Utf16Le,
+  Windows1252,
+}
+
+impl Encoding {
+  const UTF_16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+  const UTF_16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+  const UTF_8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+  pub fn decode(self, bytes: &[u8]) -> Decoded {
+    match self {
+      Self::Latin1 => Decoded {
+        lossy: false,
+        text: bytes.iter().copied().map(char::from).collect(),
+      },
+      Self::Utf8 | Self::Utf8Bom => {
+        let bytes = bytes.strip_prefix(Self::UTF_8_BOM).unwrap_or(bytes);
+
+        match String::from_utf8_lossy(bytes) {
+          Cow::Borrowed(text) => Decoded {
+            lossy: false,
+            text: text.into(),
+          },
+          Cow::Owned(text) => Decoded { lossy: true, text },
+        }
+      }
+      Self::Utf16Be => {
+        Self::decode_utf16(bytes, Self::UTF_16_BE_BOM, u16::from_be_bytes)
+      }
+      Self::Utf16Le => {
+        Self::decode_utf16(bytes, Self::UTF_16_LE_BOM, u16::from_le_bytes)
+      }
+      Self::Windows1252 => {
+        let (text, lossy) =
+          encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes);
+
+        Decoded {
+          lossy,
+          text: text.into_owned(),
+        }
+      }
+    }
+  }
+
+  fn decode_utf16(
+    bytes: &[u8],
+    bom: &[u8],
+    from_bytes: fn([u8; 2]) -> u16,
+  ) -> Decoded {
+    let bytes = bytes.strip_prefix(bom).unwrap_or(bytes);
+
+    let truncated = !bytes.len().is_multiple_of(2);
+
+    let mut lossy = truncated;
+
+    let units = bytes
+      .chunks_exact(2)
+      .map(|pair| from_bytes([pair[0], pair[1]]));
+
+    let mut text = char::decode_utf16(units)
+      .map(|c| {
+        c.unwrap_or_else(|_| {
+          lossy = true;
+          char::REPLACEMENT_CHARACTER
+        })
+      })
+      .collect::<String>();
+
+    if truncated {
+      text.push(char::REPLACEMENT_CHARACTER);
+    }
+
+    Decoded { lossy, text }
+  }
+
+  /// Guess the encoding of `bytes` from its byte order mark, if it has one,
+  /// or otherwise from its contents.
+  pub fn detect(bytes: &[u8]) -> Self {
+    if bytes.starts_with(Self::UTF_8_BOM) {
+      return Self::Utf8Bom;
+    }
+
+    if bytes.starts_with(Self::UTF_16_LE_BOM) {
+      return Self::Utf16Le;
+    }
+
+    if bytes.starts_with(Self::UTF_16_BE_BOM) {
+      return Self::Utf16Be;
+    }
+
+    // Text in UTF-16 without a byte order mark is recognizable by the zero
+    // high bytes of its ASCII characters.
+    if bytes.len() >= 2 && bytes.len().is_multiple_of(2) {
+      let pairs = bytes.len() / 2;
+
+      let (even, odd) =
+        bytes.chunks_exact(2).fold((0, 0), |(even, odd), pair| {
+          (
+            even + usize::from(pair[0] == 0),
+            odd + usize::from(pair[1] == 0),
+          )
+        });
+
+      if odd * 2 > pairs && even == 0 {
+        return Self::Utf16Le;
+      }
+
+      if even * 2 > pairs && odd == 0 {
+        return Self::Utf16Be;
+      }
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+      return Self::Utf8;
+    }
+
+    // Text that is mostly valid UTF-8 is more likely damaged UTF-8 than a
+    // single byte encoding, in which multibyte sequences rarely occur by
+    // chance. Decoding it as UTF-8 replaces the invalid bytes and warns.
+    let (multibyte, invalid) =
+      bytes
+        .utf8_chunks()
+        .fold((0, 0), |(multibyte, invalid), chunk| {
+          (
+            multibyte + chunk.valid().chars().filter(|c| !c.is_ascii()).count(),
+            invalid + usize::from(!chunk.invalid().is_empty()),
+          )
+        });
+
+    if multibyte > invalid {
+      return Self::Utf8;
+    }
+
+    // The C1 control characters that Latin-1 assigns to 0x80..=0x9F never
+    // appear in real text, whereas Windows-1252 uses them for punctuation.
+    if bytes.iter().any(|byte| (0x80..=0x9F).contains(byte)) {
+      Self::Windows1252
+    } else {
+      Self::Latin1
+    }
+  }
+
+  pub fn encode(self, text: &str) -> Result<Vec<u8>> {
+    match self {
+      Self::Latin1 => text
+        .chars()
+        .map(|character| {
+          u8::try_from(u32::from(character))
+            .ok()
+            .context(error::Unencodable {
+              character,
+              encoding: self,
+            })
+        })
+        .collect(),
+      Self::Utf8 => Ok(text.as_bytes().to_vec()),
+      Self::Utf8Bom => Ok([Self::UTF_8_BOM, text.as_bytes()].concat()),
+      Self::Utf16Be => Ok(
+        Self::UTF_16_BE_BOM
+          .iter()
+          .copied()
+          .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+          .collect(),
+      ),
+      Self::Utf16Le => Ok(
+        Self::UTF_16_LE_BOM
+          .iter()
+          .copied()
+          .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+          .collect(),
+      ),
+      Self::Windows1252 => {
+        let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(text);
+
+        if unmappable {
+          let character = text
+            .chars()
+            .find(|c| {
+              encoding_rs::WINDOWS_1252
+                .encode(c.encode_utf8(&mut [0; 4]))
+                .2
+            })
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+          return Err(
+            error::Unencodable {
+              character,
+              encoding: self,
+            }
+            .build(),
+          );
+        }
+
+        Ok(bytes.into_owned())
+      }
+    }
+  }
+}
+
+impl Display for Encoding {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Self::Latin1 => write!(f, "ISO-8859-1"),
+      Self::Utf8 => write!(f, "UTF-8"),
+      Self::Utf8Bom => write!(f, "UTF-8 BOM"),
+      Self::Utf16Be => write!(f, "UTF-16BE"),
+      Self::Utf16Le => write!(f, "UTF-16LE"),
+      Self::Windows1252 => write!(f, "Windows-1252"),
+    }
+  }
+}
+
+impl FromStr for Encoding {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.trim().to_lowercase().replace('_', "-").as_str() {
+      "cp1252" | "windows-1252" => Ok(Self::Windows1252),
+      "iso-8859-1" | "latin-1" | "latin1" => Ok(Self::Latin1),
+      "utf-16be" | "utf16be" => Ok(Self::Utf16Be),
+      "utf-16le" | "utf16le" => Ok(Self::Utf16Le),
+      "utf-8" | "utf8" => Ok(Self::Utf8),
+      "utf-8-bom" | "utf-8 bom" | "utf8-bom" => Ok(Self::Utf8Bom),
+      _ => Err(error::UnknownEncoding { name: s }.build()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detect() {
+    #[track_caller]
+    fn case(bytes: &[u8], expected: Encoding) {
+      assert_eq!(Encoding::detect(bytes), expected);
+    }
+
+    case(b"", Encoding::Utf8);
+    case(b"hello", Encoding::Utf8);
+    case("h\u{e9}llo".as_bytes(), Encoding::Utf8);
+    case(b"\xEF\xBB\xBFhello", Encoding::Utf8Bom);
+    case(b"\xFF\xFEh\0i\0", Encoding::Utf16Le);
+    case(b"\xFE\xFF\0h\0i", Encoding::Utf16Be);
+    case(b"h\0i\0", Encoding::Utf16Le);
+    case(b"\0h\0i", Encoding::Utf16Be);
+    case(b"h\xE9llo", Encoding::Latin1);
+    case(b"h\xE9llo w\xF6rld", Encoding::Latin1);
+    case(b"h\xC3\xA9llo w\xC3\xB6rld \xFF", Encoding::Utf8);
+    case(b"\x93quoted\x94", Encoding::Windows1252);
+  }
+
+  #[test]
+  fn round_trip() {
+    for (encoding, text) in [
+      (Encoding::Latin1, "h\u{e9}llo\r\nw\u{f6}rld"),
+      (Encoding::Utf8, "h\u{e9}llo\r\nw\u{f6}rld \u{1f600}"),
+      (Encoding::Utf8Bom, "h\u{e9}llo\r\nw\u{f6}rld \u{1f600}"),
+      (Encoding::Utf16Be, "h\u{e9}llo\r\nw\u{f6}rld \u{1f600}"),
+      (Encoding::Utf16Le, "h\u{e9}llo\r\nw\u{f6}rld \u{1f600}"),
+      (Encoding::Windows1252, "h\u{e9}llo\r\nw\u{f6}rld \u{20ac}"),
+    ] {
+      let bytes = encoding.encode(text).unwrap();
+
+      assert_eq!(Encoding::detect(&bytes), encoding, "{encoding}");
+
+      assert_eq!(
+        encoding.decode(&bytes),
+        Decoded {
+          lossy: false,
+          text: text.into(),
+        }
+      );
+    }
+  }
+
+  #[test]
+  fn windows_1252_punctuation() {
+    assert_eq!(
+      Encoding::Windows1252.decode(b"\x93hi\x94 \x80").text,
+      "\u{201c}hi\u{201d} \u{20ac}"
+    );
+
+    assert_eq!(
+      Encoding::Windows1252.encode("\u{20ac}").unwrap(),
+      b"\x80".to_vec()
+    );
+  }
+
+  #[test]
+  fn unencodable() {
+    assert_eq!(
+      Encoding::Latin1
+        .encode("a\u{20ac}")
+        .unwrap_err()
+        .to_string(),
+      "`\u{20ac}` cannot be encoded as ISO-8859-1"
+    );
+
+    assert_eq!(
+      Encoding::Windows1252
+        .encode("a\u{1f600}")
+        .unwrap_err()
+        .to_string(),
+      "`\u{1f600}` cannot be encoded as Windows-1252"
+    );
+  }
+
+  #[test]
+  fn lossy() {
+    assert_eq!(
+      Encoding::Utf8.decode(b"a\xFFb"),
+      Decoded {
+        lossy: true,
+        text: "a\u{fffd}b".into(),
+      }
+    );
+
+    assert_eq!(
+      Encoding::Utf16Le.decode(b"\x00\xD8a\x00"),
+      Decoded {
+        lossy: true,
+        text: "\u{fffd}a".into(),
+      }
+    );
+
+    assert_eq!(
+      Encoding::Utf16Le.decode(b"a\x00b"),
+      Decoded {
+        lossy: true,
+        text: "a\u{fffd}".into(),
+      }
+    );
+  }
+
+  #[test]
+  fn from_str() {
+    assert_eq!("utf-8".parse::<Encoding>().unwrap(), Encoding::Utf8);
+    assert_eq!("Latin1".parse::<Encoding>().unwrap(), Encoding::Latin1);
+    assert_eq!("CP1252".parse::<Encoding>().unwrap(), Encoding::Windows1252);
+    assert_eq!("utf_16le".parse::<Encoding>().unwrap(), Encoding::Utf16Le);
+    assert!("ebcdic".parse::<Encoding>().is_err());
+  }
+}
@@ -13,6 +13,19 @@ pub enum Error {
     backtrace: Option<Backtrace>,
     source: winit::error::OsError,
   },
+  #[snafu(display("cancelled"))]
+  Cancelled { backtrace: Option<Backtrace> },
+  #[snafu(display("failed to open clipboard"))]
+  OpenClipboard {
+    backtrace: Option<Backtrace>,
+    source: arboard::Error,
+  },
+  #[snafu(display("failed to create directory `{}`", path.display()))]
+  CreateDirectory {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
   #[snafu(display("failed to create surface"))]
   CreateSurface {
     backtrace: Option<Backtrace>,
@@ -23,11 +36,62 @@ pub enum Error {
     backtrace: Option<Backtrace>,
     source: wgpu::SurfaceError,
   },
+  #[snafu(display("failed to determine data directory"))]
+  DataDirectory { backtrace: Option<Backtrace> },
   #[snafu(display("failed to get device"))]
   Device {
     backtrace: Option<Backtrace>,
     source: wgpu::RequestDeviceError,
   },
+  #[snafu(display(
+    "failed to create temporary file for `{}`",
+    path.display()
+  ))]
+  CreateTempFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to lock `{}`", path.display()))]
+  LockFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to open `{}`", path.display()))]
+  OpenFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to read `{}`", path.display()))]
+  ReadFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to read clipboard"))]
+  ReadClipboard {
+    backtrace: Option<Backtrace>,
+    source: arboard::Error,
+  },
+  #[snafu(display("failed to read stdin"))]
+  ReadStdin {
+    backtrace: Option<Backtrace>,
+    source: io::Error,
+  },
+  #[snafu(display("failed to remove `{}`", path.display()))]
+  RemoveFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to replace `{}`", path.display()))]
+  RenameFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
   #[snafu(display("failed to run app"))]
   RunApp {
     backtrace: Option<Backtrace>,
@@ -38,6 +102,33 @@ pub enum Error {
     backtrace: Option<Backtrace>,
     message: String,
   },
+  #[snafu(display("`{character}` cannot be encoded as {encoding}"))]
+  Unencodable {
+    backtrace: Option<Backtrace>,
+    character: char,
+    encoding: Encoding,
+  },
+  #[snafu(display("unknown encoding `{name}`"))]
+  UnknownEncoding {
+    backtrace: Option<Backtrace>,
+    name: String,
+  },
+  #[snafu(display("failed to write `{}`", path.display()))]
+  WriteFile {
+    backtrace: Option<Backtrace>,
+    path: PathBuf,
+    source: io::Error,
+  },
+  #[snafu(display("failed to write clipboard"))]
+  WriteClipboard {
+    backtrace: Option<Backtrace>,
+    source: arboard::Error,
+  },
+  #[snafu(display("failed to write stdout"))]
+  WriteStdout {
+    backtrace: Option<Backtrace>,
+    source: io::Error,
+  },
 }
 
 impl Error {
@@ -47,4 +138,14 @@ impl Error {
     }
     .build()
   }
+
+  /// Render the error and its sources on a single line, for display in the
+  /// status line.
+  pub fn summary(&self) -> String {
+    self
+      .iter_chain()
+      .map(ToString::to_string)
+      .collect::<Vec<String>>()
+      .join(": ")
+  }
 }
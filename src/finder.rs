@@ -0,0 +1,397 @@
+use super::*;
+
+/// Number of paths the walk collects before sending them to the app.
+const BATCH: usize = 512;
+
+/// Number of paths scored against the query at a time, so that a large tree
+/// does not hold up the event loop.
+const SCORE_BATCH: usize = 2048;
+
+#[derive(Debug, PartialEq)]
+pub enum FinderAction {
+  Cancel,
+  Open(PathBuf),
+  Pending,
+}
+
+/// Quick open: a list of the files under a directory, filtered and ranked by
+/// fuzzy matching against a query. Files are found on a background thread,
+/// and arrive in batches through `add`. They are scored against the query a
+/// batch at a time by `score`, which the app calls between frames.
+#[derive(Debug)]
+pub struct Finder {
+  cancel: Arc<AtomicBool>,
+  done: bool,
+  generation: u64,
+  /// Scores and indices into `paths` of paths matching the query, best
+  /// first.
+  matches: Vec<(i64, usize)>,
+  paths: Vec<String>,
+  query: String,
+  root: PathBuf,
+  selected: usize,
+  /// Indices into `paths` of paths yet to be scored against the query.
+  unscored: Vec<usize>,
+}
+
+impl Finder {
+  /// A finder over `root`, whose results are the batches sent with
+  /// `generation`. Batches from the walks of earlier finders are ignored.
+  pub fn new(root: PathBuf, generation: u64) -> Self {
+    Self {
+      cancel: Arc::default(),
+      done: false,
+      generation,
+      matches: Vec::new(),
+      paths: Vec::new(),
+      query: String::new(),
+      root,
+      selected: 0,
+      unscored: Vec::new(),
+    }
+  }
+
+  /// Walk the root on a background thread, calling `send` with each batch of
+  /// paths found and once more when the walk is done. The walk stops early
+  /// if the finder is dropped.
+  pub fn spawn(&self, send: impl Fn(UserEvent) + Send + 'static) {
+    let (root, cancel, generation) =
+      (self.root.clone(), self.cancel.clone(), self.generation);
+
+    thread::spawn(move || {
+      Self::walk(&root, &cancel, |paths| {
+        send(UserEvent::FinderPaths { generation, paths });
+      });
+
+      send(UserEvent::FinderDone { generation });
+    });
+  }
+
+  /// Call `send` with batches of the paths of files under `root`, relative
+  /// to it, skipping hidden files and those ignored by `.gitignore` and
+  /// similar files.
+  pub fn walk(
+    root: &Path,
+    cancel: &AtomicBool,
+    mut send: impl FnMut(Vec<String>),
+  ) {
+    let mut batch = Vec::new();
+
+    for entry in ignore::WalkBuilder::new(root).build() {
+      if cancel.load(atomic::Ordering::Relaxed) {
+        return;
+      }
+
+      let Ok(entry) = entry else {
+        continue;
+      };
+
+      if !entry
+        .file_type()
+        .is_some_and(|file_type| file_type.is_file())
+      {
+        continue;
+      }
+
+      let Ok(path) = entry.path().strip_prefix(root) else {
+        continue;
+      };
+
+      batch.push(path.to_string_lossy().into_owned());
+
+      if batch.len() == BATCH {
+        send(mem::take(&mut batch));
+      }
+    }
+
+    if !batch.is_empty() {
+      send(batch);
+    }
+  }
+
+  pub fn add(&mut self, generation: u64, paths: Vec<String>) {
+    if generation != self.generation {
+      return;
+    }
+
+    let start = self.paths.len();
+
+    self.paths.extend(paths);
+
+    self.unscored.extend(start..self.paths.len());
+  }
+
+  pub fn finish(&mut self, generation: u64) {
+    if generation == self.generation {
+      self.done = true;
+    }
+  }
+
+  /// Score the next batch of unscored paths against the query, and merge
+  /// them into the matches.
+  pub fn score(&mut self) {
+    if self.unscored.is_empty() {
+      return;
+    }
+
+    let batch = self
+      .unscored
+      .split_off(self.unscored.len().saturating_sub(SCORE_BATCH));
+
+    for index in batch {
+      if let Some(score) = fuzzy_score(&self.query, &self.paths[index]) {
+        self.matches.push((score, index));
+      }
+    }
+
+    let paths = &self.paths;
+
+    // The existing matches are already sorted, which the sort takes
+    // advantage of.
+    self.matches.sort_by(|(a_score, a), (b_score, b)| {
+      b_score.cmp(a_score).then_with(|| paths[*a].cmp(&paths[*b]))
+    });
+
+    self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+  }
+
+  fn set_query(&mut self, query: String) {
+    // A path that does not match a query does not match it extended either,
+    // so typing only needs to score the matches again, along with the paths
+    // not yet scored.
+    if query.starts_with(&self.query) {
+      self
+        .unscored
+        .extend(self.matches.drain(..).map(|(_, index)| index));
+    } else {
+      self.matches.clear();
+      self.unscored = (0..self.paths.len()).collect();
+    }
+
+    self.query = query;
+    self.selected = 0;
+  }
+
+  pub fn handle_key(&mut self, key: &Key) -> FinderAction {
+    match key {
+      Key::Named(NamedKey::ArrowDown)
+        if self.selected + 1 < self.matches.len() =>
+      {
+        self.selected += 1;
+      }
+      Key::Named(NamedKey::ArrowUp) => {
+        self.selected = self.selected.saturating_sub(1);
+      }
+      Key::Named(NamedKey::Backspace) => {
+        let mut query = self.query.clone();
+        query.pop();
+        self.set_query(query);
+      }
+      Key::Named(NamedKey::Enter) => {
+        return match self.matches.get(self.selected) {
+          Some(&(_, index)) => {
+            FinderAction::Open(self.root.join(&self.paths[index]))
+          }
+          None => FinderAction::Pending,
+        };
+      }
+      Key::Named(NamedKey::Escape) => return FinderAction::Cancel,
+      Key::Named(NamedKey::Space) => self.set_query(self.query.clone() + " "),
+      Key::Character(c) => self.set_query(self.query.clone() + c),
+      _ => {}
+    }
+
+    FinderAction::Pending
+  }
+
+  /// The query and up to `rows` matches, scrolled to show the selection.
+  pub fn view(&self, rows: usize) -> FinderView {
+    let first = (self.selected + 1).saturating_sub(rows);
+
+    FinderView {
+      count: format!(
+        "{}/{}{}",
+        self.matches.len(),
+        self.paths.len(),
+        if self.done && self.unscored.is_empty() {
+          ""
+        } else {
+          "\u{2026}"
+        }
+      ),
+      matches: self
+        .matches
+        .iter()
+        .skip(first)
+        .take(rows)
+        .map(|&(_, index)| self.paths[index].clone())
+        .collect(),
+      query: self.query.clone(),
+      selected: self.selected - first,
+    }
+  }
+}
+
+impl Drop for Finder {
+  fn drop(&mut self) {
+    self.cancel.store(true, atomic::Ordering::Relaxed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  fn walk(root: &Path) -> Vec<String> {
+    let mut paths = Vec::new();
+    Finder::walk(root, &AtomicBool::new(false), |batch| paths.extend(batch));
+    paths.sort();
+    paths
+  }
+
+  #[test]
+  fn walk_respects_gitignore() {
+    let tempdir = TempDir::new().unwrap();
+
+    let root = tempdir.path();
+
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target/debug")).unwrap();
+
+    fs::write(root.join(".gitignore"), "target\n*.log\n").unwrap();
+    fs::write(root.join("src/main.rs"), "").unwrap();
+    fs::write(root.join("debug.log"), "").unwrap();
+    fs::write(root.join("target/debug/app"), "").unwrap();
+    fs::write(root.join("README.md"), "").unwrap();
+
+    assert_eq!(
+      walk(root),
+      [
+        "README.md",
+        &Path::new("src").join("main.rs").to_string_lossy()
+      ]
+    );
+  }
+
+  fn key(finder: &mut Finder, key: &str) -> FinderAction {
+    finder.handle_key(&Key::Character(key.into()))
+  }
+
+  #[test]
+  fn filter_and_open() {
+    let mut finder = Finder::new("/root".into(), 1);
+
+    finder.add(
+      1,
+      vec![
+        "src/app.rs".into(),
+        "src/main.rs".into(),
+        "Cargo.toml".into(),
+      ],
+    );
+
+    finder.add(0, vec!["src/stale.rs".into()]);
+
+    finder.score();
+
+    assert_eq!(finder.view(10).count, "3/3\u{2026}");
+
+    finder.finish(1);
+
+    key(&mut finder, "m");
+    key(&mut finder, "a");
+
+    finder.score();
+
+    assert_eq!(
+      finder.view(10),
+      FinderView {
+        count: "1/3".into(),
+        matches: vec!["src/main.rs".into()],
+        query: "ma".into(),
+        selected: 0,
+      }
+    );
+
+    finder.add(1, vec!["mailbox.rs".into()]);
+
+    finder.score();
+
+    assert_eq!(finder.view(10).matches[0], "mailbox.rs");
+
+    finder.handle_key(&Key::Named(NamedKey::ArrowDown));
+
+    assert_eq!(
+      finder.handle_key(&Key::Named(NamedKey::Enter)),
+      FinderAction::Open("/root/src/main.rs".into())
+    );
+
+    finder.handle_key(&Key::Named(NamedKey::Backspace));
+    finder.handle_key(&Key::Named(NamedKey::Backspace));
+
+    finder.score();
+
+    assert_eq!(finder.view(2).matches.len(), 2);
+
+    assert_eq!(
+      finder.handle_key(&Key::Named(NamedKey::Escape)),
+      FinderAction::Cancel
+    );
+  }
+
+  #[test]
+  fn view_scrolls_to_selection() {
+    let mut finder = Finder::new("/root".into(), 0);
+
+    finder.add(0, (0..5).map(|i| format!("{i}")).collect());
+
+    finder.score();
+
+    for _ in 0..3 {
+      finder.handle_key(&Key::Named(NamedKey::ArrowDown));
+    }
+
+    let view = finder.view(2);
+
+    assert_eq!(view.matches, ["2", "3"]);
+    assert_eq!(view.selected, 1);
+  }
+
+  #[test]
+  fn score_in_batches() {
+    let mut finder = Finder::new("/root".into(), 0);
+
+    finder.add(0, (0..=SCORE_BATCH).map(|i| format!("{i}.rs")).collect());
+
+    finder.finish(0);
+
+    finder.score();
+
+    assert_eq!(finder.matches.len(), SCORE_BATCH);
+    assert!(finder.view(1).count.ends_with('\u{2026}'));
+
+    finder.score();
+
+    assert_eq!(finder.matches.len(), SCORE_BATCH + 1);
+    assert!(!finder.view(1).count.ends_with('\u{2026}'));
+
+    key(&mut finder, "1");
+
+    assert!(finder.matches.is_empty());
+    assert_eq!(finder.unscored.len(), SCORE_BATCH + 1);
+
+    finder.score();
+    finder.score();
+
+    let matches = finder.matches.len();
+
+    key(&mut finder, "0");
+
+    assert_eq!(finder.unscored.len(), matches);
+
+    finder.handle_key(&Key::Named(NamedKey::Backspace));
+
+    assert_eq!(finder.unscored.len(), SCORE_BATCH + 1);
+  }
+}
@@ -0,0 +1,129 @@
+/// Score how well `query` matches `candidate`, or return `None` if the
+/// characters of `query` do not all appear in `candidate` in order. Matching
+/// ignores case. Matches at the start of a word, runs of consecutive
+/// matches, and matches in the file name score higher, and gaps between
+/// matches score lower. The best scoring alignment is found, rather than the
+/// first, so that `ren` matches `src/renderer.rs` at the file name.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+  const CONSECUTIVE: i64 = 32;
+
+  let query = query
+    .chars()
+    .filter(|c| !c.is_whitespace())
+    .flat_map(char::to_lowercase)
+    .collect::<Vec<char>>();
+
+  let chars = candidate.chars().collect::<Vec<char>>();
+
+  let file_name = chars
+    .iter()
+    .rposition(|&c| matches!(c, '/' | '\\'))
+    .map_or(0, |i| i + 1);
+
+  // Score for matching each character of the candidate, before counting
+  // consecutive matches and gaps.
+  let bonus = chars
+    .iter()
+    .enumerate()
+    .map(|(j, &c)| {
+      let boundary = match j.checked_sub(1).map(|j| chars[j]) {
+        None => true,
+        Some(previous) => {
+          matches!(previous, '/' | '\\' | '_' | '-' | '.' | ' ')
+            || (previous.is_lowercase() && c.is_uppercase())
+        }
+      };
+
+      let mut bonus = 16;
+
+      if j >= file_name {
+        bonus += 8;
+      }
+
+      if boundary {
+        bonus += 24;
+      }
+
+      bonus
+    })
+    .collect::<Vec<i64>>();
+
+  let lowercase = chars
+    .iter()
+    .map(|c| c.to_lowercase().next().unwrap_or(*c))
+    .collect::<Vec<char>>();
+
+  // Best score of the query so far with its last character matched at each
+  // position of the candidate.
+  let mut scores = vec![Some(0); chars.len() + 1];
+
+  for (i, &wanted) in query.iter().enumerate() {
+    let mut next = vec![None; chars.len() + 1];
+
+    // Best score of the previous query characters, less one for each
+    // character skipped since.
+    let mut gapped: Option<i64> = None;
+
+    for j in 0..chars.len() {
+      let previous = scores[j];
+
+      if lowercase[j] == wanted {
+        let consecutive = if i == 0 {
+          previous
+        } else {
+          previous.map(|score| score + CONSECUTIVE)
+        };
+
+        next[j + 1] = consecutive.max(gapped).map(|score| score + bonus[j]);
+      }
+
+      gapped = if i == 0 {
+        Some(0)
+      } else {
+        gapped.max(previous).map(|score| score - 1)
+      };
+    }
+
+    scores = next;
+  }
+
+  let score = if query.is_empty() {
+    0
+  } else {
+    scores.into_iter().flatten().max()?
+  };
+
+  // Prefer shorter paths among otherwise equal matches.
+  Some(score - chars.len().min(64) as i64 / 8)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn subsequence() {
+    assert!(fuzzy_score("abc", "a/b/c").is_some());
+    assert!(fuzzy_score("ABC", "abc").is_some());
+    assert!(fuzzy_score("", "abc").is_some());
+    assert_eq!(fuzzy_score("acb", "abc"), None);
+    assert_eq!(fuzzy_score("abcd", "abc"), None);
+  }
+
+  #[test]
+  fn ranking() {
+    let better = |query, a, b| {
+      assert!(
+        fuzzy_score(query, a) > fuzzy_score(query, b),
+        "expected `{a}` to outrank `{b}` for `{query}`",
+      );
+    };
+
+    better("app", "src/app.rs", "src/a_p_p.rs");
+    better("app", "src/app.rs", "assets/happy.txt");
+    better("main", "src/main.rs", "domain/lib.rs");
+    better("ab", "a/b.rs", "azzzzzzzzb.rs");
+    better("ren", "src/renderer.rs", "README.md/current.rs");
+    better("fs", "src/fuzzy_score.rs", "src/fuzzyscore.rs");
+  }
+}
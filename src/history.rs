@@ -0,0 +1,749 @@
+use super::*;
+
+/// Version of the undo file format, which is discarded on load if it
+/// differs.
+const VERSION: u32 = 2;
+
+/// Kinds of edit that are merged into a single undo step when they follow
+/// one another, so that undoing removes a run of typing rather than one
+/// character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Group {
+  Backspace,
+  Delete,
+  Type,
+}
+
+/// Replacement of `removed`, starting at char index `position`, with
+/// `inserted`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Edit {
+  pub inserted: String,
+  pub position: usize,
+  pub removed: String,
+}
+
+impl Edit {
+  /// The edit that reverses this one.
+  pub fn inverse(&self) -> Self {
+    Self {
+      inserted: self.removed.clone(),
+      position: self.position,
+      removed: self.inserted.clone(),
+    }
+  }
+}
+
+/// Edits undone and redone together, with the selections before and after
+/// them, the main one first.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Transaction {
+  pub after: Vec<Selection>,
+  pub before: Vec<Selection>,
+  pub edits: Vec<Edit>,
+  #[serde(skip)]
+  group: Option<Group>,
+}
+
+/// A state of the buffer, reached from its parent by `transaction`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Node {
+  children: Vec<usize>,
+  parent: Option<usize>,
+  /// Child that redo moves to, which is the one most recently visited.
+  redo: Option<usize>,
+  /// Seconds since the Unix epoch when the state was last changed.
+  time: u64,
+  transaction: Transaction,
+}
+
+/// Edits that move the buffer from one state in its history to another, and
+/// the selections to leave, the main one first.
+#[derive(Debug, PartialEq)]
+pub struct Travel {
+  pub edits: Vec<Edit>,
+  pub selections: Vec<Selection>,
+}
+
+/// Undo history of a buffer, kept as a tree so that making an edit after
+/// undoing starts a new branch rather than discarding the undone edits.
+/// Nodes are numbered in the order they were created, so moving to the
+/// previous or next number moves back or forward in time, across branches.
+///
+/// Edits are collected into a pending transaction until `end_transaction`,
+/// which adds it as a child of the current node, or merges it into the
+/// current node.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct History {
+  current: usize,
+  nodes: Vec<Node>,
+  #[serde(skip)]
+  pending: Option<Transaction>,
+  /// Node whose state was last saved.
+  saved: Option<usize>,
+  #[serde(skip)]
+  sealed: bool,
+}
+
+impl Default for History {
+  /// A history with only the initial state, which is saved.
+  fn default() -> Self {
+    Self {
+      current: 0,
+      nodes: vec![Node {
+        children: Vec::new(),
+        parent: None,
+        redo: None,
+        time: now(),
+        transaction: Transaction {
+          after: vec![Selection::cursor(0)],
+          before: vec![Selection::cursor(0)],
+          edits: Vec::new(),
+          group: None,
+        },
+      }],
+      pending: None,
+      saved: Some(0),
+      sealed: false,
+    }
+  }
+}
+
+/// Seconds since the Unix epoch.
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs()
+}
+
+/// `selections` without their goal columns, which are not worth undoing to.
+fn without_goals(selections: Vec<Selection>) -> Vec<Selection> {
+  selections
+    .into_iter()
+    .map(|selection| Selection::new(selection.anchor, selection.head))
+    .collect()
+}
+
+/// Hash of `content`, stored in the undo file to check that it belongs to
+/// the file it is loaded for.
+fn hash(content: &str) -> String {
+  blake3::hash(content.as_bytes()).to_hex().to_string()
+}
+
+#[derive(Deserialize, Serialize)]
+struct UndoFile {
+  hash: String,
+  history: History,
+  version: u32,
+}
+
+impl History {
+  /// Path of the undo file for `path`, a hidden file next to it.
+  pub fn path(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?;
+
+    let mut undo = OsString::from(".");
+    undo.push(name);
+    undo.push(".undo");
+
+    Some(path.with_file_name(undo))
+  }
+
+  /// Load the history saved for `path`, if its undo file exists and was
+  /// saved when the file held `content`.
+  pub fn load(path: &Path, content: &str) -> Option<Self> {
+    let bytes = fs::read(Self::path(path)?).ok()?;
+
+    let file = serde_json::from_slice::<UndoFile>(&bytes).ok()?;
+
+    if file.version != VERSION || file.hash != hash(content) {
+      return None;
+    }
+
+    let history = file.history;
+
+    let len = history.nodes.len();
+
+    let valid = history.current < len
+      && history.saved.is_none_or(|saved| saved < len)
+      && history.nodes.iter().enumerate().all(|(index, node)| {
+        node.parent.is_none_or(|parent| parent < index)
+          && (index == 0) == node.parent.is_none()
+          && node.children.iter().all(|&child| child < len)
+          && node.redo.is_none_or(|redo| redo < len)
+      });
+
+    (valid && history.fits(content)).then_some(Self {
+      sealed: true,
+      ..history
+    })
+  }
+
+  /// Whether every transaction applies to the state it starts from, removing
+  /// the text it says it removes and leaving its selections in range, given
+  /// that the current state holds `content`. An undo file that was cut
+  /// short or edited by hand could otherwise panic on undo or redo.
+  fn fits(&self, content: &str) -> bool {
+    fn apply(rope: &mut Rope, edit: &Edit) -> bool {
+      let Some(end) = edit.position.checked_add(edit.removed.chars().count())
+      else {
+        return false;
+      };
+
+      if end > rope.len_chars()
+        || rope.slice(edit.position..end) != edit.removed.as_str()
+      {
+        return false;
+      }
+
+      rope.remove(edit.position..end);
+      rope.insert(edit.position, &edit.inserted);
+
+      true
+    }
+
+    fn in_range(selections: &[Selection], rope: &Rope) -> bool {
+      !selections.is_empty()
+        && selections
+          .iter()
+          .all(|selection| selection.end() <= rope.len_chars())
+    }
+
+    let mut root = Rope::from_str(content);
+
+    let mut node = self.current;
+
+    while let Some(parent) = self.nodes[node].parent {
+      let transaction = &self.nodes[node].transaction;
+
+      if !in_range(&transaction.after, &root)
+        || !transaction
+          .edits
+          .iter()
+          .rev()
+          .all(|edit| apply(&mut root, &edit.inverse()))
+      {
+        return false;
+      }
+
+      node = parent;
+    }
+
+    // Parents come before their children, so each state can be built from
+    // its parent's.
+    let mut states = vec![root];
+
+    for node in &self.nodes[1..] {
+      let Some(mut state) = node.parent.map(|parent| states[parent].clone())
+      else {
+        return false;
+      };
+
+      let transaction = &node.transaction;
+
+      if !in_range(&transaction.before, &state)
+        || !transaction.edits.iter().all(|edit| apply(&mut state, edit))
+        || !in_range(&transaction.after, &state)
+      {
+        return false;
+      }
+
+      states.push(state);
+    }
+
+    true
+  }
+
+  /// Write the history to the undo file for `path`, which holds `content`.
+  pub fn save(&self, path: &Path, content: &str) -> Result {
+    let Some(undo) = Self::path(path) else {
+      return Ok(());
+    };
+
+    let file = UndoFile {
+      hash: hash(content),
+      history: Self {
+        current: self.current,
+        nodes: self.nodes.clone(),
+        pending: None,
+        saved: self.saved,
+        sealed: true,
+      },
+      version: VERSION,
+    };
+
+    let bytes = serde_json::to_vec(&file)
+      .map_err(|error| Error::internal(error.to_string()))?;
+
+    atomic_write(&undo, &bytes)
+  }
+
+  /// Whether edits have been recorded since the last `end_transaction`.
+  pub fn is_pending(&self) -> bool {
+    self.pending.is_some()
+  }
+
+  /// Add `edit` to the pending transaction. If it is the first, `before`
+  /// holds the selections it was made with, and is otherwise ignored.
+  pub fn record(&mut self, edit: Edit, before: Vec<Selection>) {
+    self
+      .pending
+      .get_or_insert_with(|| Transaction {
+        after: Vec::new(),
+        before: without_goals(before),
+        edits: Vec::new(),
+        group: None,
+      })
+      .edits
+      .push(edit);
+  }
+
+  /// Finish the pending transaction, leaving `selections`. If it belongs to
+  /// `group`, it is merged into the current node when that is the newest, is
+  /// in the same group, and ended where this one began.
+  pub fn end_transaction(
+    &mut self,
+    selections: Vec<Selection>,
+    group: Option<Group>,
+  ) {
+    let Some(mut transaction) = self.pending.take() else {
+      return;
+    };
+
+    transaction.after = without_goals(selections);
+    transaction.group = group;
+
+    let newest = self.nodes.len() - 1;
+
+    let current = &mut self.nodes[self.current];
+
+    if self.current == newest
+      && self.current != 0
+      && !self.sealed
+      && group.is_some()
+      && current.transaction.group == group
+      && current.transaction.after == transaction.before
+    {
+      current.transaction.edits.extend(transaction.edits);
+      current.transaction.after = transaction.after;
+      current.time = now();
+      return;
+    }
+
+    let index = newest + 1;
+
+    current.children.push(index);
+    current.redo = Some(index);
+
+    self.nodes.push(Node {
+      children: Vec::new(),
+      parent: Some(self.current),
+      redo: None,
+      time: now(),
+      transaction,
+    });
+
+    self.current = index;
+    self.sealed = false;
+  }
+
+  /// Whether the buffer's content is as it was when last saved.
+  pub fn is_saved(&self) -> bool {
+    self.saved == Some(self.current)
+  }
+
+  /// Record that the buffer was saved in its current state, which no later
+  /// edit will be merged into.
+  pub fn mark_saved(&mut self) {
+    self.saved = Some(self.current);
+    self.sealed = true;
+  }
+
+  /// Forget the saved state, after a change that undoing cannot reverse.
+  pub fn clear_saved(&mut self) {
+    self.saved = None;
+  }
+
+  /// Stop the next transaction from being merged into the current node, as
+  /// when the cursor moves between edits.
+  pub fn seal(&mut self) {
+    self.sealed = true;
+  }
+
+  /// Move to the state `target`, undoing back to the newest state it shares
+  /// with the current one and redoing from there. Redo then follows the path
+  /// taken.
+  fn travel(&mut self, target: usize) -> Option<Travel> {
+    if target == self.current || target >= self.nodes.len() {
+      return None;
+    }
+
+    let ancestors = |mut node: usize| {
+      let mut path = vec![node];
+
+      while let Some(parent) = self.nodes[node].parent {
+        path.push(parent);
+        node = parent;
+      }
+
+      path
+    };
+
+    let mut up = ancestors(self.current);
+
+    let mut down = ancestors(target);
+
+    while up.len() > 1
+      && down.len() > 1
+      && up[up.len() - 2] == down[down.len() - 2]
+    {
+      up.pop();
+      down.pop();
+    }
+
+    // Both paths now end at the shared state, which is not moved through.
+    up.pop();
+    down.pop();
+    down.reverse();
+
+    let mut edits = Vec::new();
+
+    let mut selections = &Vec::new();
+
+    for &node in &up {
+      let transaction = &self.nodes[node].transaction;
+      edits.extend(transaction.edits.iter().rev().map(Edit::inverse));
+      selections = &transaction.before;
+    }
+
+    for &node in &down {
+      let transaction = &self.nodes[node].transaction;
+      edits.extend(transaction.edits.iter().cloned());
+      selections = &transaction.after;
+    }
+
+    let selections = selections.clone();
+
+    for &node in up.iter().chain(&down) {
+      if let Some(parent) = self.nodes[node].parent {
+        self.nodes[parent].redo = Some(node);
+      }
+    }
+
+    self.current = target;
+    self.sealed = true;
+
+    Some(Travel { edits, selections })
+  }
+
+  pub fn undo(&mut self) -> Option<Travel> {
+    self.travel(self.nodes[self.current].parent?)
+  }
+
+  pub fn redo(&mut self) -> Option<Travel> {
+    self.travel(self.nodes[self.current].redo?)
+  }
+
+  /// Move to the state created before the current one, which may be on
+  /// another branch.
+  pub fn earlier(&mut self) -> Option<Travel> {
+    self.travel(self.current.checked_sub(1)?)
+  }
+
+  /// Move to the state created after the current one, which may be on
+  /// another branch.
+  pub fn later(&mut self) -> Option<Travel> {
+    self.travel(self.current + 1)
+  }
+
+  /// Move to the sibling of the current state `offset` places along, and
+  /// return the travel along with the sibling's position among its siblings,
+  /// counting from one, and their number.
+  pub fn switch_branch(
+    &mut self,
+    offset: isize,
+  ) -> Option<(Travel, usize, usize)> {
+    let parent = self.nodes[self.current].parent?;
+
+    let siblings = &self.nodes[parent].children;
+
+    let count = siblings.len();
+
+    if count < 2 {
+      return None;
+    }
+
+    let index = siblings.iter().position(|&node| node == self.current)?;
+
+    let index = (index as isize + offset).rem_euclid(count as isize) as usize;
+
+    let target = siblings[index];
+
+    Some((self.travel(target)?, index + 1, count))
+  }
+
+  /// Move to the state as it was `ago` before now, which is the newest state
+  /// last changed no later than that, or the initial state if there is none.
+  pub fn go_back(&mut self, ago: Duration) -> Option<Travel> {
+    let time = now().saturating_sub(ago.as_secs());
+
+    let target = self
+      .nodes
+      .iter()
+      .rposition(|node| node.time <= time)
+      .unwrap_or_default();
+
+    self.travel(target)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  fn insert(position: usize, text: &str) -> Edit {
+    Edit {
+      inserted: text.into(),
+      position,
+      removed: String::new(),
+    }
+  }
+
+  fn cursor(char_idx: usize) -> Vec<Selection> {
+    vec![Selection::cursor(char_idx)]
+  }
+
+  fn edit(history: &mut History, position: usize, text: &str) {
+    history.record(insert(position, text), cursor(position));
+    history.end_transaction(cursor(position + text.len()), None);
+  }
+
+  fn summary(travel: Option<Travel>) -> Vec<String> {
+    travel
+      .unwrap()
+      .edits
+      .into_iter()
+      .map(|edit| {
+        if edit.removed.is_empty() {
+          format!("+{}", edit.inserted)
+        } else {
+          format!("-{}", edit.removed)
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn merge_groups() {
+    let mut history = History::default();
+
+    history.record(insert(0, "a"), cursor(0));
+    history.end_transaction(cursor(1), Some(Group::Type));
+
+    history.record(insert(1, "b"), cursor(1));
+    history.end_transaction(cursor(2), Some(Group::Type));
+
+    assert_eq!(history.nodes.len(), 2);
+    assert_eq!(history.nodes[1].transaction.edits.len(), 2);
+
+    history.record(insert(2, "\n"), cursor(2));
+    history.end_transaction(cursor(3), None);
+
+    history.record(insert(3, "c"), cursor(3));
+    history.end_transaction(cursor(4), Some(Group::Type));
+
+    history.seal();
+
+    history.record(insert(4, "d"), cursor(4));
+    history.end_transaction(cursor(5), Some(Group::Type));
+
+    history.record(insert(0, "e"), cursor(0));
+    history.end_transaction(cursor(1), Some(Group::Type));
+
+    assert_eq!(history.nodes.len(), 6);
+  }
+
+  #[test]
+  fn saved_state() {
+    let mut history = History::default();
+
+    assert!(history.is_saved());
+
+    edit(&mut history, 0, "a");
+
+    assert!(!history.is_saved());
+
+    history.undo();
+
+    assert!(history.is_saved());
+
+    history.redo();
+    history.mark_saved();
+    history.undo();
+
+    edit(&mut history, 0, "b");
+
+    assert!(!history.is_saved());
+
+    history.undo();
+
+    assert!(!history.is_saved());
+
+    history.redo();
+    history.switch_branch(1);
+
+    assert!(history.is_saved());
+  }
+
+  #[test]
+  fn branches_are_kept() {
+    let mut history = History::default();
+
+    edit(&mut history, 0, "a");
+    edit(&mut history, 1, "b");
+
+    assert_eq!(summary(history.undo()), ["-b"]);
+
+    edit(&mut history, 1, "c");
+
+    assert_eq!(history.current, 3);
+
+    let (travel, index, count) = history.switch_branch(1).unwrap();
+
+    assert_eq!(summary(Some(travel)), ["-c", "+b"]);
+    assert_eq!((index, count), (1, 2));
+
+    history.undo();
+
+    assert_eq!(summary(history.redo()), ["+b"]);
+
+    assert_eq!(summary(history.later()), ["-b", "+c"]);
+
+    assert_eq!(summary(history.earlier()), ["-c", "+b"]);
+
+    assert_eq!(summary(history.earlier()), ["-b"]);
+
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_none());
+    assert!(history.earlier().is_none());
+
+    assert_eq!(summary(history.later()), ["+a"]);
+    assert_eq!(summary(history.later()), ["+b"]);
+    assert_eq!(summary(history.later()), ["-b", "+c"]);
+    assert!(history.later().is_none());
+  }
+
+  #[test]
+  fn travel_cursor() {
+    let mut history = History::default();
+
+    edit(&mut history, 0, "ab");
+
+    assert_eq!(history.undo().unwrap().selections, cursor(0));
+    assert_eq!(history.redo().unwrap().selections, cursor(2));
+  }
+
+  #[test]
+  fn go_back() {
+    let mut history = History::default();
+
+    edit(&mut history, 0, "a");
+    edit(&mut history, 1, "b");
+    edit(&mut history, 2, "c");
+
+    let now = now();
+
+    history.nodes[0].time = now - 3600;
+    history.nodes[1].time = now - 1200;
+    history.nodes[2].time = now - 300;
+    history.nodes[3].time = now;
+
+    assert_eq!(
+      summary(history.go_back(Duration::from_secs(600))),
+      ["-c", "-b"]
+    );
+
+    assert_eq!(history.current, 1);
+
+    assert!(history.go_back(Duration::from_secs(1800)).is_some());
+
+    assert_eq!(history.current, 0);
+
+    assert!(history.go_back(Duration::from_secs(7200)).is_none());
+  }
+
+  #[test]
+  fn save_and_load() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    assert_eq!(
+      History::path(&path),
+      Some(tempdir.path().join(".foo.txt.undo"))
+    );
+
+    let mut history = History::default();
+
+    edit(&mut history, 0, "a");
+    edit(&mut history, 1, "b");
+    history.undo();
+    edit(&mut history, 1, "c");
+    history.mark_saved();
+
+    history.save(&path, "ac").unwrap();
+
+    assert_eq!(History::load(&path, "ab"), None);
+
+    let mut loaded = History::load(&path, "ac").unwrap();
+
+    assert_eq!(loaded.nodes, history.nodes);
+    assert_eq!(loaded.current, history.current);
+    assert!(loaded.is_saved());
+
+    let (travel, _, _) = loaded.switch_branch(1).unwrap();
+
+    assert_eq!(summary(Some(travel)), ["-c", "+b"]);
+  }
+
+  #[test]
+  fn load_rejects_edits_that_do_not_fit() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let history = || {
+      let mut history = History::default();
+      edit(&mut history, 0, "a");
+      edit(&mut history, 1, "b");
+      history
+    };
+
+    history().save(&path, "ab").unwrap();
+
+    assert!(History::load(&path, "ab").is_some());
+
+    for (position, removed) in [(5, ""), (0, "x")] {
+      let mut damaged = history();
+
+      damaged.nodes[2].transaction.edits[0] = Edit {
+        inserted: "b".into(),
+        position,
+        removed: removed.into(),
+      };
+
+      damaged.save(&path, "ab").unwrap();
+
+      assert_eq!(History::load(&path, "ab"), None, "{position} {removed}");
+    }
+
+    let mut damaged = history();
+
+    damaged.nodes[1].transaction.after = cursor(10);
+
+    damaged.save(&path, "ab").unwrap();
+
+    assert_eq!(History::load(&path, "ab"), None);
+  }
+}
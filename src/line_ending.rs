@@ -0,0 +1,142 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LineEnding {
+  Cr,
+  Crlf,
+  #[default]
+  Lf,
+}
+
+impl LineEnding {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::Cr => "\r",
+      Self::Crlf => "\r\n",
+      Self::Lf => "\n",
+    }
+  }
+
+  /// Replace every line ending in `text` with `self`, returning the converted
+  /// text along with `char_idx` mapped to the corresponding position in it.
+  pub fn convert(self, text: &Rope, char_idx: usize) -> (Rope, usize) {
+    let mut converted = String::with_capacity(text.len_bytes());
+
+    let mut mapped = None;
+
+    let mut chars = text.chars().enumerate().peekable();
+
+    while let Some((i, c)) = chars.next() {
+      if i >= char_idx && mapped.is_none() {
+        mapped = Some(converted.chars().count());
+      }
+
+      match c {
+        '\r' => {
+          if let Some((_, '\n')) = chars.peek() {
+            chars.next();
+          }
+
+          converted.push_str(self.as_str());
+        }
+        '\n' => converted.push_str(self.as_str()),
+        c => converted.push(c),
+      }
+    }
+
+    let converted = Rope::from_str(&converted);
+
+    let mapped = mapped.unwrap_or_else(|| converted.len_chars());
+
+    (converted, mapped)
+  }
+
+  /// The most common line ending in `text`, or `None` if it contains no line
+  /// breaks. Ties are broken in favor of `Lf`, then `Crlf`.
+  pub fn detect(text: &Rope) -> Option<Self> {
+    let (mut cr, mut crlf, mut lf) = (0, 0, 0);
+
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+      match c {
+        '\r' => {
+          if let Some('\n') = chars.peek() {
+            chars.next();
+            crlf += 1;
+          } else {
+            cr += 1;
+          }
+        }
+        '\n' => lf += 1,
+        _ => {}
+      }
+    }
+
+    if lf + crlf + cr == 0 {
+      None
+    } else if lf >= crlf && lf >= cr {
+      Some(Self::Lf)
+    } else if crlf >= cr {
+      Some(Self::Crlf)
+    } else {
+      Some(Self::Cr)
+    }
+  }
+}
+
+impl Display for LineEnding {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Self::Cr => write!(f, "CR"),
+      Self::Crlf => write!(f, "CRLF"),
+      Self::Lf => write!(f, "LF"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detect() {
+    #[track_caller]
+    fn case(text: &str, expected: Option<LineEnding>) {
+      assert_eq!(LineEnding::detect(&Rope::from_str(text)), expected);
+    }
+
+    case("", None);
+    case("foo", None);
+    case("foo\nbar\n", Some(LineEnding::Lf));
+    case("foo\r\nbar\r\n", Some(LineEnding::Crlf));
+    case("foo\rbar\r", Some(LineEnding::Cr));
+    case("a\r\nb\r\nc\nd", Some(LineEnding::Crlf));
+    case("a\r\nb\nc", Some(LineEnding::Lf));
+    case("a\r\nb\rc", Some(LineEnding::Crlf));
+  }
+
+  #[test]
+  fn convert() {
+    #[track_caller]
+    fn case(
+      text: &str,
+      char_idx: usize,
+      line_ending: LineEnding,
+      expected: &str,
+      expected_idx: usize,
+    ) {
+      let (converted, mapped) =
+        line_ending.convert(&Rope::from_str(text), char_idx);
+
+      assert_eq!(converted.to_string(), expected);
+      assert_eq!(mapped, expected_idx);
+    }
+
+    case("a\nb\r\nc\rd", 0, LineEnding::Lf, "a\nb\nc\nd", 0);
+    case("a\nb\r\nc\rd", 7, LineEnding::Lf, "a\nb\nc\nd", 6);
+    case("a\nb\r\nc\rd", 8, LineEnding::Lf, "a\nb\nc\nd", 7);
+    case("a\nb", 2, LineEnding::Crlf, "a\r\nb", 3);
+    case("a\r\nb", 3, LineEnding::Cr, "a\rb", 2);
+  }
+}
@@ -1,51 +1,241 @@
 use {
-  crate::{app::App, error::Error, renderer::Renderer},
+  crate::{
+    after_save::AfterSave,
+    app::App,
+    arguments::Arguments,
+    atomic_write::atomic_write,
+    buffer::Buffer,
+    click::Click,
+    clipboard::Clipboard,
+    data_directory::data_directory,
+    direction::Direction,
+    encoding::{Decoded, Encoding},
+    error::Error,
+    finder::{Finder, FinderAction},
+    fuzzy_score::fuzzy_score,
+    history::{Edit, Group, History, Travel},
+    line_ending::LineEnding,
+    memory_clipboard::MemoryClipboard,
+    metrics::Metrics,
+    momentum::Momentum,
+    orientation::Orientation,
+    pane::Pane,
+    parse_duration::parse_duration,
+    pipe::Pipe,
+    prompt::{Prompt, PromptAction, PromptKind},
+    quads::Quads,
+    rect::Rect,
+    renderer::Renderer,
+    rope_ext::RopeExt,
+    scratch::Scratch,
+    selection::Selection,
+    split::Split,
+    stamp::Stamp,
+    swap::{Recovery, Snapshot, Swap},
+    system_clipboard::SystemClipboard,
+    tab::Tab,
+    user_event::UserEvent,
+    view::{FinderView, PaneView, View},
+    watcher::Watcher,
+    word_ranges::{WORD_SEPARATORS, word_ranges},
+  },
+  clap::Parser,
   ropey::Rope,
-  snafu::{Backtrace, ErrorCompat, ResultExt, Snafu},
+  serde::{Deserialize, Serialize},
+  similar::TextDiff,
+  snafu::{Backtrace, ErrorCompat, OptionExt, ResultExt, Snafu},
   std::{
-    sync::Arc,
-    time::{Duration, Instant},
+    borrow::Cow,
+    collections::BTreeMap,
+    env,
+    ffi::{OsStr, OsString},
+    fmt::{self, Display, Formatter},
+    fs::{self, File},
+    io::{self, IsTerminal, Read, Write},
+    iter, mem,
+    ops::{Range, RangeInclusive},
+    panic,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::{
+      Arc, Mutex, MutexGuard, PoisonError, TryLockError,
+      atomic::{self, AtomicBool},
+      mpsc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+  },
+  tempfile::NamedTempFile,
+  unicode_segmentation::{
+    GraphemeCursor, GraphemeIncomplete, UnicodeSegmentation,
   },
   wgpu::{
     Color, LoadOp, Operations, PowerPreference, RenderPassColorAttachment,
     RenderPassDescriptor, RequestAdapterOptions, StoreOp, SurfaceConfiguration,
-    TextureUsages, TextureViewDescriptor, util::StagingBelt,
+    TextureUsages, TextureViewDescriptor,
+    util::{DeviceExt, StagingBelt},
   },
   wgpu_glyph::{
-    GlyphBrush, GlyphBrushBuilder, Section, Text, ab_glyph::FontArc,
+    GlyphBrush, GlyphBrushBuilder, HorizontalAlign, Layout, Region, Section,
+    Text,
+    ab_glyph::{Font, FontArc, ScaleFont},
   },
   winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::{ElementState, WindowEvent},
-    event_loop::{ActiveEventLoop, EventLoop},
-    keyboard::{Key, NamedKey},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+      ElementState, Ime, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+    },
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    keyboard::{Key, ModifiersState, NamedKey},
+    platform::modifier_supplement::KeyEventExtModifierSupplement,
     window::{Window, WindowAttributes, WindowId},
   },
 };
 
+mod after_save;
 mod app;
+mod arguments;
+mod atomic_write;
+mod buffer;
+mod click;
+mod clipboard;
+mod data_directory;
+mod direction;
+mod encoding;
 mod error;
+mod finder;
+mod fuzzy_score;
+mod history;
+mod line_ending;
+mod memory_clipboard;
+mod metrics;
+mod momentum;
+mod orientation;
+mod pane;
+mod parse_duration;
+mod pipe;
+mod prompt;
+mod quads;
+mod rect;
 mod renderer;
+mod rope_ext;
+mod scratch;
+mod selection;
+mod split;
+mod stamp;
+mod swap;
+mod system_clipboard;
+mod tab;
+mod user_event;
+mod view;
+mod watcher;
+mod word_ranges;
 
 type Result<T = (), E = Error> = std::result::Result<T, E>;
 
 fn run() -> Result {
-  let event_loop = EventLoop::with_user_event()
+  let arguments = Arguments::parse();
+
+  let event_loop = EventLoop::<UserEvent>::with_user_event()
     .build()
     .context(error::EventLoopBuild)?;
 
-  let mut app = App::new();
+  let data_directory = match data_directory() {
+    Ok(data_directory) => Some(data_directory),
+    Err(error) => {
+      eprintln!(
+        "warning: swap files and scratch persistence disabled: {}",
+        error.summary()
+      );
+      None
+    }
+  };
+
+  let mut paths = arguments.paths.into_iter();
+
+  let mut app = match paths.next() {
+    Some(path) => App::open(path)?,
+    None if stdin_is_piped() => App::pipe(io::stdin().lock())?,
+    None => match &data_directory {
+      Some(data_directory) => {
+        App::scratch(Scratch::new(data_directory.join("scratch")))?
+      }
+      None => App::new(),
+    },
+  };
+
+  for path in paths {
+    app.open_file(path)?;
+  }
+
+  app.set_scroll_margin(arguments.scroll_margin);
+  app.set_word_separators(arguments.word_separators);
+
+  if let Some(data_directory) = &data_directory {
+    match Swap::new(data_directory.join("swap")) {
+      Ok(swap) => {
+        swap.install_panic_hook();
+        app.set_swap(swap);
+      }
+      Err(error) => {
+        eprintln!("warning: swap files disabled: {}", error.summary());
+      }
+    }
+  }
+
+  match SystemClipboard::new() {
+    Ok(clipboard) => app.set_clipboard(clipboard),
+    Err(error) => {
+      eprintln!("warning: system clipboard unavailable: {}", error.summary());
+    }
+  }
+
+  app.set_proxy(event_loop.create_proxy());
+
+  let proxy = event_loop.create_proxy();
+
+  app.set_watcher(Watcher::new(move |path| {
+    proxy.send_event(UserEvent::FileChanged(path)).ok();
+  }));
 
   event_loop.run_app(&mut app).context(error::RunApp)?;
 
-  if let Some(error) = app.error() {
-    return Err(error);
+  if let Some(output) = app.finish()? {
+    io::stdout()
+      .lock()
+      .write_all(&output)
+      .context(error::WriteStdout)?;
   }
 
   Ok(())
 }
 
+/// Whether stdin is a pipe or file, rather than a terminal or a character
+/// device like `/dev/null`, which is what desktop environments usually
+/// connect to stdin.
+fn stdin_is_piped() -> bool {
+  let stdin = io::stdin();
+
+  if stdin.is_terminal() {
+    return false;
+  }
+
+  #[cfg(unix)]
+  {
+    use std::os::{fd::AsFd, unix::fs::FileTypeExt};
+
+    if let Ok(fd) = stdin.as_fd().try_clone_to_owned()
+      && let Ok(metadata) = File::from(fd).metadata()
+    {
+      return !metadata.file_type().is_char_device();
+    }
+  }
+
+  true
+}
+
 fn main() {
   env_logger::init();
 
@@ -61,11 +251,11 @@ fn main() {
       eprintln!("- {err}");
     }
 
-    if let Some(backtrace) = err.backtrace() {
-      if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
-        eprintln!("backtrace:");
-        eprintln!("{backtrace}");
-      }
+    if let Some(backtrace) = err.backtrace()
+      && backtrace.status() == std::backtrace::BacktraceStatus::Captured
+    {
+      eprintln!("backtrace:");
+      eprintln!("{backtrace}");
     }
 
     std::process::exit(1);
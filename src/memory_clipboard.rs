@@ -0,0 +1,29 @@
+use super::*;
+
+/// A clipboard private to the app, used when the system clipboard cannot be
+/// opened, and in tests.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+  primary: Option<String>,
+  text: Option<String>,
+}
+
+impl Clipboard for MemoryClipboard {
+  fn get(&mut self) -> Result<Option<String>> {
+    Ok(self.text.clone())
+  }
+
+  fn set(&mut self, text: String) -> Result {
+    self.text = Some(text);
+    Ok(())
+  }
+
+  fn get_primary(&mut self) -> Result<Option<String>> {
+    Ok(self.primary.clone())
+  }
+
+  fn set_primary(&mut self, text: String) -> Result {
+    self.primary = Some(text);
+    Ok(())
+  }
+}
@@ -0,0 +1,34 @@
+use super::*;
+
+/// Dimensions of the editor font, in pixels. The font is monospace, so every
+/// glyph has the same advance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Metrics {
+  pub advance: f32,
+  pub font_size: f32,
+  pub line_height: f32,
+}
+
+impl Metrics {
+  pub fn new(font: &FontArc, font_size: f32) -> Self {
+    let scaled = font.as_scaled(font_size);
+
+    Self {
+      advance: scaled.h_advance(font.glyph_id('0')),
+      font_size,
+      line_height: scaled.height() + scaled.line_gap(),
+    }
+  }
+}
+
+impl Default for Metrics {
+  /// Metrics of the bundled font at 32 pixels, used until the renderer has
+  /// measured it.
+  fn default() -> Self {
+    Self {
+      advance: 16.0,
+      font_size: 32.0,
+      line_height: 32.0,
+    }
+  }
+}
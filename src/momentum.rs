@@ -0,0 +1,204 @@
+use super::*;
+
+/// Scrolling a pane still has to do after the wheel or touchpad moves it,
+/// played out over the following frames. Wheel clicks ease towards where
+/// they scroll to, and a touchpad fling carries on after the fingers lift,
+/// slowing down as it goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Momentum {
+  /// Distance left to scroll by wheel clicks, in pixels.
+  remaining: (f32, f32),
+  /// When the touchpad last scrolled, to measure its velocity.
+  touched_at: Option<Instant>,
+  /// Whether fingers are on the touchpad, which holds back the fling.
+  touching: bool,
+  /// Velocity of the touchpad, in pixels per second.
+  velocity: (f32, f32),
+}
+
+impl Momentum {
+  /// Fraction of the remaining wheel distance scrolled per second, as the
+  /// rate of an exponential.
+  const EASING: f32 = 20.0;
+
+  /// Rate at which a fling slows down, per second.
+  const FRICTION: f32 = 4.0;
+
+  /// Longest pause between the last touchpad movement and the fingers
+  /// lifting for which the touchpad is flung.
+  const FLING_WINDOW: Duration = Duration::from_millis(50);
+
+  /// Shortest interval between touchpad movements used to measure velocity,
+  /// about one frame, so that bunched up events do not spike it.
+  const MIN_INTERVAL: Duration = Duration::from_millis(8);
+
+  /// Speed below which a fling stops, in pixels per second.
+  const MIN_VELOCITY: f32 = 20.0;
+
+  pub fn is_moving(&self) -> bool {
+    self.remaining != (0.0, 0.0)
+      || (!self.touching && self.velocity != (0.0, 0.0))
+  }
+
+  /// Stop flinging after the fingers lift from the touchpad, unless they
+  /// were still moving.
+  pub fn lift(&mut self, now: Instant) {
+    self.touching = false;
+
+    if self
+      .touched_at
+      .is_none_or(|touched_at| now - touched_at > Self::FLING_WINDOW)
+    {
+      self.velocity = (0.0, 0.0);
+    }
+  }
+
+  /// Distance to scroll for the `elapsed` time since the last frame.
+  pub fn step(&mut self, elapsed: Duration) -> (f32, f32) {
+    let seconds = elapsed.as_secs_f32();
+
+    let ease = 1.0 - (-Self::EASING * seconds).exp();
+
+    let friction = (-Self::FRICTION * seconds).exp();
+
+    let touching = self.touching;
+
+    let axis = |remaining: &mut f32, velocity: &mut f32| {
+      // Finish once less than half a pixel is left, which is never reached
+      // by easing alone.
+      let mut distance = if remaining.abs() < 0.5 {
+        *remaining
+      } else {
+        *remaining * ease
+      };
+
+      *remaining -= distance;
+
+      if !touching {
+        distance += *velocity * seconds;
+
+        *velocity *= friction;
+
+        if velocity.abs() < Self::MIN_VELOCITY {
+          *velocity = 0.0;
+        }
+      }
+
+      distance
+    };
+
+    (
+      axis(&mut self.remaining.0, &mut self.velocity.0),
+      axis(&mut self.remaining.1, &mut self.velocity.1),
+    )
+  }
+
+  /// Record the touchpad scrolling by `delta` pixels at `now`, which the
+  /// caller scrolls straight away, to measure how fast it is going.
+  pub fn touch(&mut self, delta: (f32, f32), now: Instant) {
+    let seconds = self
+      .touched_at
+      .map_or(Self::MIN_INTERVAL, |touched_at| now - touched_at)
+      .max(Self::MIN_INTERVAL)
+      .as_secs_f32();
+
+    // Average with the previous velocity to smooth out uneven events.
+    let velocity = |delta: f32, previous: f32| {
+      if self.touching {
+        (delta / seconds + previous) / 2.0
+      } else {
+        delta / seconds
+      }
+    };
+
+    self.velocity = (
+      velocity(delta.0, self.velocity.0),
+      velocity(delta.1, self.velocity.1),
+    );
+
+    self.remaining = (0.0, 0.0);
+    self.touched_at = Some(now);
+    self.touching = true;
+  }
+
+  /// Ease the scroll position `delta` pixels further, as when the wheel
+  /// clicks, stopping any fling.
+  pub fn wheel(&mut self, delta: (f32, f32)) {
+    self.remaining.0 += delta.0;
+    self.remaining.1 += delta.1;
+    self.touching = false;
+    self.velocity = (0.0, 0.0);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Total distance scrolled by `momentum` over `frames` frames of `frame`.
+  fn play(
+    momentum: &mut Momentum,
+    frames: usize,
+    frame: Duration,
+  ) -> (f32, f32) {
+    (0..frames).fold((0.0, 0.0), |(x, y), _| {
+      let (dx, dy) = momentum.step(frame);
+      (x + dx, y + dy)
+    })
+  }
+
+  #[test]
+  fn wheel_eases_to_target() {
+    let mut momentum = Momentum::default();
+
+    assert!(!momentum.is_moving());
+
+    momentum.wheel((0.0, 96.0));
+
+    assert!(momentum.is_moving());
+
+    let (_, first) = momentum.step(Duration::from_millis(16));
+
+    assert!(first > 0.0 && first < 96.0);
+
+    let (x, y) = play(&mut momentum, 100, Duration::from_millis(16));
+
+    assert_eq!(x, 0.0);
+    assert!((first + y - 96.0).abs() < 0.001);
+    assert!(!momentum.is_moving());
+  }
+
+  #[test]
+  fn fling_after_lift() {
+    let mut momentum = Momentum::default();
+
+    let start = Instant::now();
+
+    momentum.touch((0.0, 10.0), start);
+    momentum.touch((0.0, 10.0), start + Duration::from_millis(10));
+
+    assert!(!momentum.is_moving());
+    assert_eq!(momentum.step(Duration::from_millis(16)), (0.0, 0.0));
+
+    momentum.lift(start + Duration::from_millis(20));
+
+    assert!(momentum.is_moving());
+
+    let (_, distance) = play(&mut momentum, 500, Duration::from_millis(16));
+
+    assert!(distance > 100.0 && distance < 1000.0);
+    assert!(!momentum.is_moving());
+  }
+
+  #[test]
+  fn no_fling_after_pause() {
+    let mut momentum = Momentum::default();
+
+    let start = Instant::now();
+
+    momentum.touch((0.0, 10.0), start);
+    momentum.lift(start + Duration::from_millis(200));
+
+    assert!(!momentum.is_moving());
+  }
+}
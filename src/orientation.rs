@@ -0,0 +1,8 @@
+/// How a split arranges its two halves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+  /// One above the other, divided by a horizontal line.
+  Horizontal,
+  /// Side by side, divided by a vertical line.
+  Vertical,
+}
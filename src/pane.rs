@@ -0,0 +1,39 @@
+use super::*;
+
+/// A view of a buffer in one part of a split, with its own cursor and scroll
+/// position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pane {
+  /// Selection anchor while the pane is not focused, kept like `cursor`.
+  pub anchor: Option<usize>,
+  pub buffer: u64,
+  /// Cursor position while the pane is not focused. The focused pane's
+  /// cursor is kept in its buffer, where editing commands act on it.
+  pub cursor: usize,
+  /// Scrolling by the wheel or touchpad still to be played out.
+  pub momentum: Momentum,
+  /// Index of the first visible line.
+  pub scroll: usize,
+  /// Index of the first visible column, when lines are too long to fit.
+  pub scroll_column: usize,
+  /// Distance in pixels that the view is scrolled past `scroll_column` and
+  /// `scroll`, which is only ever partway into a column or line when
+  /// scrolling with the wheel or touchpad.
+  pub scroll_offset: (f32, f32),
+}
+
+impl Pane {
+  /// Space between the edges of the pane and its text.
+  pub const PADDING: f32 = 24.0;
+
+  /// Default number of lines and columns kept visible around the cursor.
+  pub const SCROLL_MARGIN: usize = 3;
+
+  /// Scroll back to the start of the buffer, as when showing another one.
+  pub fn reset_scroll(&mut self) {
+    self.momentum = Momentum::default();
+    self.scroll = 0;
+    self.scroll_column = 0;
+    self.scroll_offset = (0.0, 0.0);
+  }
+}
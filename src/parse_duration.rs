@@ -0,0 +1,72 @@
+use super::*;
+
+/// Parse a duration such as `10m`, `1h 30m`, or `2 days ago`. A number
+/// without a unit is in minutes.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+  let input = input.trim().to_lowercase();
+
+  let input = input.strip_suffix("ago").unwrap_or(&input).trim();
+
+  if input.is_empty() {
+    return None;
+  }
+
+  let mut seconds = 0u64;
+
+  let mut rest = input;
+
+  while !rest.is_empty() {
+    let digits = rest
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(rest.len());
+
+    let number = rest[..digits].parse::<u64>().ok()?;
+
+    rest = rest[digits..].trim_start();
+
+    let letters = rest
+      .find(|c: char| !c.is_alphabetic())
+      .unwrap_or(rest.len());
+
+    let unit = match &rest[..letters] {
+      "s" | "sec" | "secs" | "second" | "seconds" => 1,
+      "" | "m" | "min" | "mins" | "minute" | "minutes" => 60,
+      "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+      "d" | "day" | "days" => 24 * 60 * 60,
+      _ => return None,
+    };
+
+    seconds = seconds.checked_add(number.checked_mul(unit)?)?;
+
+    rest = rest[letters..].trim_start_matches([' ', ',']);
+  }
+
+  Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    #[track_caller]
+    fn case(input: &str, seconds: Option<u64>) {
+      assert_eq!(parse_duration(input), seconds.map(Duration::from_secs));
+    }
+
+    case("10", Some(600));
+    case("10m", Some(600));
+    case("10 minutes ago", Some(600));
+    case("30s", Some(30));
+    case("1h 30m", Some(5400));
+    case("1h, 30 min", Some(5400));
+    case("2 days", Some(172_800));
+    case("1D", Some(86_400));
+    case("", None);
+    case("ago", None);
+    case("m", None);
+    case("10 fortnights", None);
+    case("-5m", None);
+  }
+}
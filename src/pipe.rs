@@ -0,0 +1,8 @@
+/// State of a buffer read from stdin, which is written to stdout when
+/// committed.
+#[derive(Debug, PartialEq)]
+pub enum Pipe {
+  Cancelled,
+  Committed(Vec<u8>),
+  Open,
+}
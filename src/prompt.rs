@@ -0,0 +1,127 @@
+use super::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PromptKind {
+  Encoding,
+  FileChanged(PathBuf),
+  GoBack,
+  LineEnding,
+  Open,
+  Recover(Option<PathBuf>),
+  SaveAs,
+  SwitchBuffer,
+  UnsavedChanges,
+}
+
+impl PromptKind {
+  /// Keys accepted by prompts that pick from a fixed set of choices rather
+  /// than taking free-form input.
+  fn choices(&self) -> Option<&'static [&'static str]> {
+    match self {
+      Self::Encoding => None,
+      Self::FileChanged(_) => Some(&["r", "k", "d"]),
+      Self::GoBack => None,
+      Self::LineEnding => Some(&["l", "c", "r"]),
+      Self::Open => None,
+      Self::Recover(_) => Some(&["r", "d", "k"]),
+      Self::SaveAs => None,
+      Self::SwitchBuffer => None,
+      Self::UnsavedChanges => Some(&["s", "d", "c"]),
+    }
+  }
+
+  fn label(&self) -> String {
+    match self {
+      Self::Encoding => "Encoding".into(),
+      Self::FileChanged(path) => format!(
+        "`{}` changed on disk: [r]eload, [k]eep mine, or [d]iff",
+        path.display()
+      ),
+      Self::GoBack => "Go back by (e.g. 10m, 1h)".into(),
+      Self::LineEnding => "Line endings: [l]f, [c]rlf, or c[r]".into(),
+      Self::Open => "Open".into(),
+      Self::Recover(path) => format!(
+        "Recovered unsaved changes to {}: [r]estore, [d]iscard, or [k]eep",
+        path
+          .as_ref()
+          .map(|path| format!("`{}`", path.display()))
+          .unwrap_or_else(|| "untitled buffer".into())
+      ),
+      Self::SaveAs => "Save as".into(),
+      Self::SwitchBuffer => "Buffer".into(),
+      Self::UnsavedChanges => {
+        "Unsaved changes: [s]ave, [d]iscard, or [c]ancel".into()
+      }
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum PromptAction {
+  Cancel,
+  Pending,
+  Submit(String),
+}
+
+#[derive(Debug)]
+pub struct Prompt {
+  pub input: String,
+  pub kind: PromptKind,
+}
+
+impl Prompt {
+  pub fn new(kind: PromptKind, input: impl Into<String>) -> Self {
+    Self {
+      input: input.into(),
+      kind,
+    }
+  }
+
+  pub fn handle_key(&mut self, key: &Key) -> PromptAction {
+    if let Some(choices) = self.kind.choices() {
+      return match key {
+        Key::Named(NamedKey::Escape) => PromptAction::Cancel,
+        Key::Character(c) => {
+          let c = c.to_lowercase();
+
+          if choices.contains(&c.as_str()) {
+            PromptAction::Submit(c)
+          } else {
+            PromptAction::Pending
+          }
+        }
+        _ => PromptAction::Pending,
+      };
+    }
+
+    match key {
+      Key::Named(NamedKey::Backspace) => {
+        self.input.pop();
+      }
+      Key::Named(NamedKey::Enter) => {
+        return PromptAction::Submit(self.input.clone());
+      }
+      Key::Named(NamedKey::Escape) => return PromptAction::Cancel,
+      Key::Named(NamedKey::Space) => self.input.push(' '),
+      Key::Character(c) => self.input.push_str(c),
+      _ => {}
+    }
+
+    PromptAction::Pending
+  }
+
+  /// Append the first line of `text` to the input of a free-form prompt.
+  pub fn paste(&mut self, text: &str) {
+    if self.kind.choices().is_none() {
+      self.input.push_str(text.lines().next().unwrap_or_default());
+    }
+  }
+
+  pub fn message(&self) -> String {
+    if self.kind.choices().is_some() {
+      self.kind.label()
+    } else {
+      format!("{}: {}", self.kind.label(), self.input)
+    }
+  }
+}
@@ -0,0 +1,177 @@
+use super::*;
+
+const SHADER: &str = "
+struct Vertex {
+  @location(0) position: vec2<f32>,
+  @location(1) color: vec4<f32>,
+};
+
+struct Fragment {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> Fragment {
+  return Fragment(vec4<f32>(vertex.position, 0.0, 1.0), vertex.color);
+}
+
+@fragment
+fn fragment(fragment: Fragment) -> @location(0) vec4<f32> {
+  return fragment.color;
+}
+";
+
+/// Position and color, as six floats.
+const VERTEX_SIZE: u64 = 6 * 4;
+
+/// Draws solid rectangles, such as tab backgrounds, beneath the text.
+pub struct Quads {
+  pipeline: wgpu::RenderPipeline,
+  vertices: Vec<f32>,
+}
+
+impl Quads {
+  pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("Quads"),
+      source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+
+    let pipeline =
+      device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Quads"),
+        layout: None,
+        vertex: wgpu::VertexState {
+          module: &module,
+          entry_point: Some("vertex"),
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+          buffers: &[wgpu::VertexBufferLayout {
+            array_stride: VERTEX_SIZE,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![
+              0 => Float32x2,
+              1 => Float32x4,
+            ],
+          }],
+        },
+        fragment: Some(wgpu::FragmentState {
+          module: &module,
+          entry_point: Some("fragment"),
+          compilation_options: wgpu::PipelineCompilationOptions::default(),
+          targets: &[Some(wgpu::ColorTargetState {
+            format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+          })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+      });
+
+    Self {
+      pipeline,
+      vertices: Vec::new(),
+    }
+  }
+
+  /// Queue a rectangle with its top left corner at `(x, y)`, in pixels.
+  pub fn queue(
+    &mut self,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    color: [f32; 4],
+  ) {
+    for (x, y) in [
+      (x, y),
+      (x + width, y),
+      (x, y + height),
+      (x, y + height),
+      (x + width, y),
+      (x + width, y + height),
+    ] {
+      self.vertices.extend([x, y]);
+      self.vertices.extend(color);
+    }
+  }
+
+  /// Queue a rectangle like `queue`, cut off where it leaves `clip`.
+  pub fn queue_clipped(
+    &mut self,
+    clip: Rect,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+    color: [f32; 4],
+  ) {
+    let (left, top) = (x.max(clip.x), y.max(clip.y));
+
+    let (right, bottom) = (
+      (x + width).min(clip.right()),
+      (y + height).min(clip.bottom()),
+    );
+
+    if right > left && bottom > top {
+      self.queue((left, top), (right - left, bottom - top), color);
+    }
+  }
+
+  /// Draw and clear the queued rectangles onto `view`, which is
+  /// `target_width` by `target_height` pixels.
+  pub fn draw_queued(
+    &mut self,
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    target_width: u32,
+    target_height: u32,
+  ) {
+    if self.vertices.is_empty() {
+      return;
+    }
+
+    let (width, height) = (target_width as f32, target_height as f32);
+
+    // Convert pixel coordinates, with y pointing down, to clip space.
+    let contents = self
+      .vertices
+      .chunks(6)
+      .flat_map(|vertex| {
+        let mut vertex = <[f32; 6]>::try_from(vertex).unwrap_or_default();
+        vertex[0] = vertex[0] / width * 2.0 - 1.0;
+        vertex[1] = 1.0 - vertex[1] / height * 2.0;
+        vertex
+      })
+      .flat_map(f32::to_ne_bytes)
+      .collect::<Vec<u8>>();
+
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("Quads"),
+      contents: &contents,
+      usage: wgpu::BufferUsages::VERTEX,
+    });
+
+    let mut pass = encoder.begin_render_pass(&RenderPassDescriptor {
+      label: Some("Quads"),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: Operations {
+          load: LoadOp::Load,
+          store: StoreOp::Store,
+        },
+      })],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+
+    pass.set_pipeline(&self.pipeline);
+    pass.set_vertex_buffer(0, buffer.slice(..));
+    pass.draw(0..(contents.len() as u64 / VERTEX_SIZE) as u32, 0..1);
+
+    self.vertices.clear();
+  }
+}
@@ -0,0 +1,108 @@
+use super::*;
+
+/// An axis-aligned rectangle, in pixels from the top left of the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+  pub height: f32,
+  pub width: f32,
+  pub x: f32,
+  pub y: f32,
+}
+
+impl Rect {
+  pub fn bottom(self) -> f32 {
+    self.y + self.height
+  }
+
+  pub fn contains(self, x: f32, y: f32) -> bool {
+    (self.x..self.right()).contains(&x) && (self.y..self.bottom()).contains(&y)
+  }
+
+  pub fn right(self) -> f32 {
+    self.x + self.width
+  }
+
+  /// Divide the rectangle in two, giving `ratio` of it to the first part.
+  pub fn split(self, orientation: Orientation, ratio: f32) -> (Self, Self) {
+    match orientation {
+      Orientation::Horizontal => {
+        let height = (self.height * ratio).round();
+
+        (
+          Self { height, ..self },
+          Self {
+            height: self.height - height,
+            y: self.y + height,
+            ..self
+          },
+        )
+      }
+      Orientation::Vertical => {
+        let width = (self.width * ratio).round();
+
+        (
+          Self { width, ..self },
+          Self {
+            width: self.width - width,
+            x: self.x + width,
+            ..self
+          },
+        )
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RECT: Rect = Rect {
+    height: 100.0,
+    width: 200.0,
+    x: 10.0,
+    y: 20.0,
+  };
+
+  #[test]
+  fn contains() {
+    assert!(RECT.contains(10.0, 20.0));
+    assert!(RECT.contains(209.0, 119.0));
+    assert!(!RECT.contains(210.0, 50.0));
+    assert!(!RECT.contains(50.0, 120.0));
+    assert!(!RECT.contains(9.0, 50.0));
+  }
+
+  #[test]
+  fn split() {
+    assert_eq!(
+      RECT.split(Orientation::Horizontal, 0.25),
+      (
+        Rect {
+          height: 25.0,
+          ..RECT
+        },
+        Rect {
+          height: 75.0,
+          y: 45.0,
+          ..RECT
+        },
+      )
+    );
+
+    assert_eq!(
+      RECT.split(Orientation::Vertical, 0.5),
+      (
+        Rect {
+          width: 100.0,
+          ..RECT
+        },
+        Rect {
+          width: 100.0,
+          x: 110.0,
+          ..RECT
+        },
+      )
+    );
+  }
+}
@@ -1,11 +1,15 @@
 use super::*;
 
 pub struct Renderer {
+  advance: f32,
   config: SurfaceConfiguration,
   cursor_blink_timer: Instant,
   cursor_visible: bool,
   device: wgpu::Device,
+  font: FontArc,
   glyph_brush: GlyphBrush<()>,
+  metrics: Metrics,
+  quads: Quads,
   queue: wgpu::Queue,
   size: winit::dpi::PhysicalSize<u32>,
   staging_belt: wgpu::util::StagingBelt,
@@ -74,15 +78,27 @@ impl Renderer {
           Error::internal(format!("failed to load font: {error}"))
         })?;
 
+    // Horizontal advance of a glyph at a scale of one pixel, the same for
+    // every glyph in a monospace font.
+    let advance = font.as_scaled(1.0).h_advance(font.glyph_id('0'));
+
+    let metrics = Metrics::new(&font, 32.0);
+
     let glyph_brush =
-      GlyphBrushBuilder::using_font(font).build(&device, format);
+      GlyphBrushBuilder::using_font(font.clone()).build(&device, format);
+
+    let quads = Quads::new(&device, format);
 
     Ok(Self {
+      advance,
       config,
       cursor_blink_timer: Instant::now(),
       cursor_visible: true,
       device,
+      font,
       glyph_brush,
+      metrics,
+      quads,
       queue,
       size,
       staging_belt,
@@ -90,6 +106,289 @@ impl Renderer {
     })
   }
 
+  pub fn metrics(&self) -> Metrics {
+    self.metrics
+  }
+
+  /// Queue the separators between panes, and the selections, the underline
+  /// of text being composed, and the cursors of each pane where they are
+  /// visible. The focused pane's cursor blinks, and the others are drawn
+  /// grey.
+  fn queue_pane_quads(&mut self, panes: &[PaneView]) {
+    let (width, bottom) = (
+      self.size.width as f32,
+      self.size.height as f32 - View::STATUS_HEIGHT,
+    );
+
+    let separator = [0.8, 0.8, 0.8, 1.0];
+
+    for pane in panes {
+      let rect = pane.rect;
+
+      if rect.right() < width {
+        self.quads.queue(
+          (rect.right() - 1.0, rect.y),
+          (1.0, rect.height),
+          separator,
+        );
+      }
+
+      if rect.bottom() < bottom {
+        self.quads.queue(
+          (rect.x, rect.bottom() - 1.0),
+          (rect.width, 1.0),
+          separator,
+        );
+      }
+
+      // Where the text starts, scrolled partway into its first line and
+      // column.
+      let (left, top) = (
+        rect.x + Pane::PADDING - pane.scroll_offset.0,
+        rect.y + Pane::PADDING - pane.scroll_offset.1,
+      );
+
+      let selection_color = if pane.focused {
+        [0.7, 0.82, 1.0, 1.0]
+      } else {
+        [0.85, 0.85, 0.85, 1.0]
+      };
+
+      for (row, columns) in &pane.selection {
+        let Some(line) = pane.lines.get(*row) else {
+          continue;
+        };
+
+        let x = |column: usize| {
+          let width = self.text_width(line, column);
+
+          // A selected line ending is drawn as a space.
+          if column > line.chars().count() {
+            width + self.advance * self.metrics.font_size
+          } else {
+            width
+          }
+        };
+
+        let (start, end) = (x(columns.start), x(columns.end));
+
+        self.quads.queue_clipped(
+          rect,
+          (left + start, top + *row as f32 * self.metrics.line_height),
+          (end - start, self.metrics.line_height),
+          selection_color,
+        );
+      }
+
+      if let Some((row, columns)) = &pane.preedit
+        && let Some(line) = pane.lines.get(*row)
+      {
+        let (start, end) = (
+          self.text_width(line, columns.start),
+          self.text_width(line, columns.end),
+        );
+
+        self.quads.queue_clipped(
+          rect,
+          (
+            left + start,
+            top + (*row + 1) as f32 * self.metrics.line_height - 2.0,
+          ),
+          (end - start, 2.0),
+          [0.0, 0.0, 0.0, 1.0],
+        );
+      }
+
+      if pane.focused && !self.cursor_visible {
+        continue;
+      }
+
+      let color = if pane.focused {
+        [0.0, 0.0, 0.0, 1.0]
+      } else {
+        [0.6, 0.6, 0.6, 1.0]
+      };
+
+      for &(row, column) in pane.cursor.iter().chain(&pane.other_cursors) {
+        let x = pane
+          .lines
+          .get(row)
+          .map_or(0.0, |line| self.text_width(line, column));
+
+        self.quads.queue_clipped(
+          rect,
+          (left + x, top + row as f32 * self.metrics.line_height),
+          (2.0, self.metrics.line_height),
+          color,
+        );
+      }
+    }
+  }
+
+  /// Width of the first `chars` chars of `line`, as laid out by the glyph
+  /// brush. Combining marks have no advance, so this may be less than the
+  /// width of a cell per char.
+  pub fn text_width(&self, line: &str, chars: usize) -> f32 {
+    let font = self.font.as_scaled(self.metrics.font_size);
+
+    line
+      .chars()
+      .take(chars)
+      .map(|c| font.h_advance(font.glyph_id(c)))
+      .sum()
+  }
+
+  /// Draw a pane's lines, clipped to its rectangle.
+  fn draw_pane_text(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    pane: &PaneView,
+  ) -> Result {
+    let rect = pane.rect;
+
+    for (row, line) in pane.lines.iter().enumerate() {
+      self.glyph_brush.queue(Section {
+        screen_position: (
+          rect.x + Pane::PADDING - pane.scroll_offset.0,
+          rect.y + Pane::PADDING - pane.scroll_offset.1
+            + row as f32 * self.metrics.line_height,
+        ),
+        bounds: (
+          rect.width - Pane::PADDING + pane.scroll_offset.0,
+          self.metrics.line_height,
+        ),
+        text: vec![
+          Text::new(line)
+            .with_color([0.0, 0.0, 0.0, 1.0])
+            .with_scale(self.metrics.font_size),
+        ],
+        layout: Layout::default_single_line(),
+      });
+    }
+
+    let (x, y) = (
+      (rect.x.max(0.0) as u32).min(self.size.width),
+      (rect.y.max(0.0) as u32).min(self.size.height),
+    );
+
+    let region = Region {
+      x,
+      y,
+      width: (rect.width.max(0.0) as u32).min(self.size.width - x),
+      height: (rect.height.max(0.0) as u32).min(self.size.height - y),
+    };
+
+    self
+      .glyph_brush
+      .draw_queued_with_transform_and_scissoring(
+        &self.device,
+        &mut self.staging_belt,
+        encoder,
+        target,
+        wgpu_glyph::orthographic_projection(self.size.width, self.size.height),
+        region,
+      )
+      .map_err(|e| Error::internal(format!("Failed to render text: {}", e)))
+  }
+
+  /// Draw the quick open overlay, on top of everything else, centered below
+  /// the tab bar.
+  fn draw_finder(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    finder: &FinderView,
+  ) -> Result {
+    let font_size = 24.0;
+
+    let (padding, row_height) = (12.0, 36.0);
+
+    let window_width = self.size.width as f32;
+
+    let width = (window_width - padding * 4.0).clamp(0.0, 800.0);
+
+    let (x, y) = ((window_width - width) / 2.0, Tab::HEIGHT + padding);
+
+    let height = row_height * (finder.matches.len() + 1) as f32 + padding;
+
+    self.quads.queue(
+      (x - 1.0, y - 1.0),
+      (width + 2.0, height + 2.0),
+      [0.7, 0.7, 0.7, 1.0],
+    );
+
+    self
+      .quads
+      .queue((x, y), (width, height), [0.97, 0.97, 0.97, 1.0]);
+
+    if !finder.matches.is_empty() {
+      self.quads.queue(
+        (x, y + row_height * (finder.selected + 1) as f32),
+        (width, row_height),
+        [0.85, 0.9, 1.0, 1.0],
+      );
+    }
+
+    self.quads.draw_queued(
+      &self.device,
+      encoder,
+      target,
+      self.size.width,
+      self.size.height,
+    );
+
+    let text_y =
+      |row: usize| y + row as f32 * row_height + (row_height - font_size) / 2.0;
+
+    self.glyph_brush.queue(Section {
+      screen_position: (x + padding, text_y(0)),
+      bounds: (width - padding * 2.0, row_height),
+      text: vec![
+        Text::new(&format!("> {}", finder.query))
+          .with_color([0.0, 0.0, 0.0, 1.0])
+          .with_scale(font_size),
+      ],
+      layout: Layout::default_single_line(),
+    });
+
+    self.glyph_brush.queue(Section {
+      screen_position: (x + width - padding, text_y(0)),
+      bounds: (width - padding * 2.0, row_height),
+      text: vec![
+        Text::new(&finder.count)
+          .with_color([0.5, 0.5, 0.5, 1.0])
+          .with_scale(font_size),
+      ],
+      layout: Layout::default_single_line().h_align(HorizontalAlign::Right),
+    });
+
+    for (index, path) in finder.matches.iter().enumerate() {
+      self.glyph_brush.queue(Section {
+        screen_position: (x + padding, text_y(index + 1)),
+        bounds: (width - padding * 2.0, row_height),
+        text: vec![
+          Text::new(path)
+            .with_color([0.2, 0.2, 0.2, 1.0])
+            .with_scale(font_size),
+        ],
+        layout: Layout::default_single_line(),
+      });
+    }
+
+    self
+      .glyph_brush
+      .draw_queued(
+        &self.device,
+        &mut self.staging_belt,
+        encoder,
+        target,
+        self.size.width,
+        self.size.height,
+      )
+      .map_err(|e| Error::internal(format!("Failed to render text: {}", e)))
+  }
+
   pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
     if new_size.width > 0 && new_size.height > 0 {
       self.size = new_size;
@@ -99,11 +398,63 @@ impl Renderer {
     }
   }
 
-  pub fn render(
-    &mut self,
-    text_content: &str,
-    cursor_position: usize,
-  ) -> Result {
+  /// Queue the tab bar, truncating names with an ellipsis to fit each tab.
+  fn queue_tabs(&mut self, tabs: &[Tab]) {
+    let font_size = 20.0;
+
+    let padding = 12.0;
+
+    let window_width = self.size.width as f32;
+
+    let width = Tab::width(tabs.len(), window_width);
+
+    self.quads.queue(
+      (0.0, 0.0),
+      (window_width, Tab::HEIGHT),
+      [0.9, 0.9, 0.9, 1.0],
+    );
+
+    let max_chars =
+      ((width - padding * 2.0) / (self.advance * font_size)).max(1.0) as usize;
+
+    for (index, tab) in tabs.iter().enumerate() {
+      let x = index as f32 * width;
+
+      if tab.active {
+        self
+          .quads
+          .queue((x, 0.0), (width, Tab::HEIGHT), [1.0, 1.0, 1.0, 1.0]);
+      } else {
+        self.quads.queue(
+          (x + width - 1.0, 8.0),
+          (1.0, Tab::HEIGHT - 16.0),
+          [0.7, 0.7, 0.7, 1.0],
+        );
+      }
+
+      let mut label = tab.label();
+
+      if label.chars().count() > max_chars {
+        label =
+          label.chars().take(max_chars - 1).collect::<String>() + "\u{2026}";
+      }
+
+      let color = if tab.active {
+        [0.0, 0.0, 0.0, 1.0]
+      } else {
+        [0.4, 0.4, 0.4, 1.0]
+      };
+
+      self.glyph_brush.queue(Section {
+        screen_position: (x + padding, (Tab::HEIGHT - font_size) / 2.0),
+        bounds: (width - padding, Tab::HEIGHT),
+        text: vec![Text::new(&label).with_color(color).with_scale(font_size)],
+        layout: Layout::default_single_line(),
+      });
+    }
+  }
+
+  pub fn render(&mut self, view: &View) -> Result {
     if self.cursor_blink_timer.elapsed() > Duration::from_millis(500) {
       self.cursor_visible = !self.cursor_visible;
       self.cursor_blink_timer = Instant::now();
@@ -114,7 +465,7 @@ impl Renderer {
       .get_current_texture()
       .context(error::CurrentTexture)?;
 
-    let view = output
+    let target = output
       .texture
       .create_view(&TextureViewDescriptor::default());
 
@@ -128,7 +479,7 @@ impl Renderer {
     encoder.begin_render_pass(&RenderPassDescriptor {
       label: Some("Clear Pass"),
       color_attachments: &[Some(RenderPassColorAttachment {
-        view: &view,
+        view: &target,
         resolve_target: None,
         ops: Operations {
           load: LoadOp::Clear(Color {
@@ -145,64 +496,67 @@ impl Renderer {
       occlusion_query_set: None,
     });
 
-    let text_before_cursor = &text_content[0..cursor_position];
+    self.queue_tabs(&view.tabs);
+
+    self.queue_pane_quads(&view.panes);
+
+    self.quads.draw_queued(
+      &self.device,
+      &mut encoder,
+      &target,
+      self.size.width,
+      self.size.height,
+    );
+
+    let x_margin = 30.0;
 
-    let font_size = 32.0;
+    let status_font_size = 24.0;
 
-    let (x_margin, y_margin) = (30.0, 40.0);
+    let status_y = self.size.height as f32 - View::STATUS_HEIGHT
+      + (View::STATUS_HEIGHT - status_font_size) / 2.0;
 
     self.glyph_brush.queue(Section {
-      screen_position: (x_margin, y_margin),
-      bounds: (self.size.width as f32, self.size.height as f32),
+      screen_position: (self.size.width as f32 - x_margin, status_y),
+      bounds: (self.size.width as f32, status_font_size * 2.0),
       text: vec![
-        Text::new(text_content)
-          .with_color([0.0, 0.0, 0.0, 1.0])
-          .with_scale(font_size),
+        Text::new(&view.indicators)
+          .with_color([0.3, 0.3, 0.3, 1.0])
+          .with_scale(status_font_size),
       ],
-      ..Section::default()
+      layout: Layout::default_single_line().h_align(HorizontalAlign::Right),
     });
 
+    if let Some(status_line) = &view.status_line {
+      self.glyph_brush.queue(Section {
+        screen_position: (x_margin, status_y),
+        bounds: (self.size.width as f32, status_font_size * 2.0),
+        text: vec![
+          Text::new(status_line)
+            .with_color([0.3, 0.3, 0.3, 1.0])
+            .with_scale(status_font_size),
+        ],
+        ..Section::default()
+      });
+    }
+
     self
       .glyph_brush
       .draw_queued(
         &self.device,
         &mut self.staging_belt,
         &mut encoder,
-        &view,
+        &target,
         self.size.width,
         self.size.height,
       )
       .map_err(|e| Error::internal(format!("Failed to render text: {}", e)))?;
 
-    if self.cursor_visible {
-      let char_width = 15.2;
-
-      let cursor_x = x_margin + (text_before_cursor.len() as f32 * char_width);
-
-      self.glyph_brush.queue(Section {
-        screen_position: (cursor_x, y_margin),
-        bounds: (self.size.width as f32, self.size.height as f32),
-        text: vec![
-          Text::new("|")
-            .with_color([0.0, 0.0, 0.0, 1.0])
-            .with_scale(font_size),
-        ],
-        ..Section::default()
-      });
+    for pane in &view.panes {
+      self.draw_pane_text(&mut encoder, &target, pane)?;
+    }
 
-      self
-        .glyph_brush
-        .draw_queued(
-          &self.device,
-          &mut self.staging_belt,
-          &mut encoder,
-          &view,
-          self.size.width,
-          self.size.height,
-        )
-        .map_err(|e| {
-          Error::internal(format!("Failed to render cursor: {}", e))
-        })?;
+    if let Some(finder) = &view.finder {
+      self.draw_finder(&mut encoder, &target, finder)?;
     }
 
     self.staging_belt.finish();
@@ -0,0 +1,181 @@
+use super::*;
+
+/// Extended grapheme cluster boundaries in a rope, which is where the cursor
+/// may be placed. A cluster is what the user sees as one character, like
+/// `e` followed by a combining accent, an emoji with modifiers, or `\r\n`.
+pub trait RopeExt {
+  fn is_grapheme_boundary(&self, char_idx: usize) -> bool;
+
+  /// Char index of the first boundary after `char_idx`, or the end of the
+  /// rope.
+  fn next_grapheme_boundary(&self, char_idx: usize) -> usize;
+
+  /// Char index of the last boundary before `char_idx`, or zero.
+  fn previous_grapheme_boundary(&self, char_idx: usize) -> usize;
+
+  /// `char_idx` if it is a boundary, or else the boundary before it.
+  fn snap_to_grapheme_boundary(&self, char_idx: usize) -> usize {
+    if self.is_grapheme_boundary(char_idx) {
+      char_idx
+    } else {
+      self.previous_grapheme_boundary(char_idx)
+    }
+  }
+}
+
+impl RopeExt for Rope {
+  // `GraphemeCursor::is_boundary` wrongly joins a prepended character to a
+  // line ending at the start of the next chunk, so find the cluster
+  // containing `char_idx` instead.
+  fn is_grapheme_boundary(&self, char_idx: usize) -> bool {
+    char_idx == 0
+      || self.next_grapheme_boundary(self.previous_grapheme_boundary(char_idx))
+        == char_idx
+  }
+
+  fn next_grapheme_boundary(&self, char_idx: usize) -> usize {
+    let byte_idx = self.char_to_byte(char_idx);
+
+    let (mut chunk, mut chunk_byte_idx, mut chunk_char_idx, _) =
+      self.chunk_at_byte(byte_idx);
+
+    let mut cursor = GraphemeCursor::new(byte_idx, self.len_bytes(), true);
+
+    loop {
+      match cursor.next_boundary(chunk, chunk_byte_idx) {
+        Ok(None) => return self.len_chars(),
+        Ok(Some(n)) => {
+          return chunk_char_idx
+            + ropey::str_utils::byte_to_char_idx(chunk, n - chunk_byte_idx);
+        }
+        Err(GraphemeIncomplete::NextChunk) => {
+          chunk_byte_idx += chunk.len();
+          (chunk, _, chunk_char_idx, _) = self.chunk_at_byte(chunk_byte_idx);
+        }
+        Err(GraphemeIncomplete::PreContext(n)) => {
+          let (context, context_byte_idx, _, _) = self.chunk_at_byte(n - 1);
+          cursor.provide_context(context, context_byte_idx);
+        }
+        Err(error) => unreachable!("unexpected grapheme error: {error:?}"),
+      }
+    }
+  }
+
+  fn previous_grapheme_boundary(&self, char_idx: usize) -> usize {
+    let byte_idx = self.char_to_byte(char_idx);
+
+    let (mut chunk, mut chunk_byte_idx, mut chunk_char_idx, _) =
+      self.chunk_at_byte(byte_idx);
+
+    let mut cursor = GraphemeCursor::new(byte_idx, self.len_bytes(), true);
+
+    loop {
+      match cursor.prev_boundary(chunk, chunk_byte_idx) {
+        Ok(None) => return 0,
+        Ok(Some(n)) => {
+          return chunk_char_idx
+            + ropey::str_utils::byte_to_char_idx(chunk, n - chunk_byte_idx);
+        }
+        Err(GraphemeIncomplete::PrevChunk) => {
+          (chunk, chunk_byte_idx, chunk_char_idx, _) =
+            self.chunk_at_byte(chunk_byte_idx - 1);
+        }
+        Err(GraphemeIncomplete::PreContext(n)) => {
+          let (context, context_byte_idx, _, _) = self.chunk_at_byte(n - 1);
+          cursor.provide_context(context, context_byte_idx);
+        }
+        Err(error) => unreachable!("unexpected grapheme error: {error:?}"),
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*, proptest::prelude::*, unicode_segmentation::UnicodeSegmentation,
+  };
+
+  /// Char indices of the grapheme boundaries in `text`, including both ends.
+  fn boundaries(text: &str) -> Vec<usize> {
+    text
+      .grapheme_indices(true)
+      .map(|(byte_idx, _)| text[..byte_idx].chars().count())
+      .chain([text.chars().count()])
+      .collect()
+  }
+
+  /// A rope holding `text`, built by inserting `pieces` so that its chunks
+  /// split the text in different places.
+  fn rope(text: &str, pieces: usize) -> Rope {
+    let mut rope = Rope::new();
+
+    let chars = text.chars().collect::<Vec<char>>();
+
+    for piece in chars.chunks(chars.len().div_ceil(pieces.max(1)).max(1)) {
+      rope.insert(rope.len_chars(), &piece.iter().collect::<String>());
+    }
+
+    rope
+  }
+
+  #[test]
+  fn clusters() {
+    let rope = Rope::from_str("e\u{301}a\r\n\u{1F469}\u{200D}\u{1F4BB}!");
+
+    assert_eq!(rope.next_grapheme_boundary(0), 2);
+    assert_eq!(rope.next_grapheme_boundary(2), 3);
+    assert_eq!(rope.next_grapheme_boundary(3), 5);
+    assert_eq!(rope.next_grapheme_boundary(5), 8);
+    assert_eq!(rope.next_grapheme_boundary(8), 9);
+    assert_eq!(rope.next_grapheme_boundary(9), 9);
+
+    assert_eq!(rope.previous_grapheme_boundary(9), 8);
+    assert_eq!(rope.previous_grapheme_boundary(8), 5);
+    assert_eq!(rope.previous_grapheme_boundary(7), 5);
+    assert_eq!(rope.previous_grapheme_boundary(1), 0);
+    assert_eq!(rope.previous_grapheme_boundary(0), 0);
+
+    assert!(!rope.is_grapheme_boundary(4));
+    assert!(rope.is_grapheme_boundary(9));
+    assert_eq!(rope.snap_to_grapheme_boundary(4), 3);
+    assert_eq!(rope.snap_to_grapheme_boundary(5), 5);
+  }
+
+  proptest! {
+    #[test]
+    fn boundaries_match_str(
+      text in "(\\PC|\\p{M}|\u{200D}|\r\n?|\n){0,600}",
+      pieces in 1usize..40,
+    ) {
+      let rope = rope(&text, pieces);
+
+      let expected = boundaries(&text);
+
+      let mut forward = vec![0];
+
+      while let Some(&last) = forward.last() && last < rope.len_chars() {
+        forward.push(rope.next_grapheme_boundary(last));
+      }
+
+      prop_assert_eq!(&forward, &expected);
+
+      let mut backward = vec![rope.len_chars()];
+
+      while let Some(&last) = backward.last() && last > 0 {
+        backward.push(rope.previous_grapheme_boundary(last));
+      }
+
+      backward.reverse();
+
+      prop_assert_eq!(&backward, &expected);
+
+      for char_idx in 0..=rope.len_chars() {
+        prop_assert_eq!(
+          rope.is_grapheme_boundary(char_idx),
+          expected.contains(&char_idx)
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,226 @@
+use super::*;
+
+const HISTORY_LIMIT: usize = 32;
+
+/// Storage for the scratch buffer, which has no file of its own and is
+/// instead saved automatically and restored on the next launch.
+///
+/// Each time the scratch buffer is loaded, its previous contents are also
+/// copied into `history`, which keeps the last `HISTORY_LIMIT` sessions.
+#[derive(Debug)]
+pub struct Scratch {
+  directory: PathBuf,
+}
+
+impl Scratch {
+  pub fn new(directory: PathBuf) -> Self {
+    Self { directory }
+  }
+
+  fn content_path(&self) -> PathBuf {
+    self.directory.join("scratch.txt")
+  }
+
+  fn cursor_path(&self) -> PathBuf {
+    self.directory.join("cursor")
+  }
+
+  fn history_directory(&self) -> PathBuf {
+    self.directory.join("history")
+  }
+
+  /// Paths of archived sessions, oldest first.
+  pub fn history(&self) -> Result<Vec<PathBuf>> {
+    let directory = self.history_directory();
+
+    let entries = match fs::read_dir(&directory) {
+      Ok(entries) => entries,
+      Err(error) if error.kind() == io::ErrorKind::NotFound => {
+        return Ok(Vec::new());
+      }
+      Err(error) => {
+        return Err(error).context(error::ReadFile { path: directory });
+      }
+    };
+
+    let mut history = entries
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<io::Result<Vec<PathBuf>>>()
+      .context(error::ReadFile { path: &directory })?;
+
+    history.sort();
+
+    Ok(history)
+  }
+
+  /// Load the scratch buffer and cursor position saved by the last session,
+  /// and archive them in the history.
+  pub fn load(&self) -> Result<(Rope, usize)> {
+    let path = self.content_path();
+
+    let content = match fs::read_to_string(&path) {
+      Ok(content) => content,
+      Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+      Err(error) => return Err(error).context(error::ReadFile { path }),
+    };
+
+    let cursor = fs::read_to_string(self.cursor_path())
+      .ok()
+      .and_then(|cursor| cursor.trim().parse::<usize>().ok())
+      .unwrap_or_default();
+
+    if !content.is_empty() {
+      self.archive(&content)?;
+    }
+
+    let content = Rope::from_str(&content);
+
+    let cursor = cursor.min(content.len_chars());
+
+    Ok((content, cursor))
+  }
+
+  fn archive(&self, content: &str) -> Result {
+    let history = self.history()?;
+
+    if let Some(latest) = history.last()
+      && fs::read_to_string(latest).is_ok_and(|latest| latest == content)
+    {
+      return Ok(());
+    }
+
+    let directory = self.history_directory();
+
+    fs::create_dir_all(&directory)
+      .context(error::CreateDirectory { path: &directory })?;
+
+    let mut timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_nanos();
+
+    let path = loop {
+      let path = directory.join(format!("{timestamp:020}.txt"));
+
+      if !path.exists() {
+        break path;
+      }
+
+      timestamp += 1;
+    };
+
+    atomic_write(&path, content.as_bytes())?;
+
+    let history = self.history()?;
+
+    for path in history
+      .iter()
+      .take(history.len().saturating_sub(HISTORY_LIMIT))
+    {
+      fs::remove_file(path).context(error::RemoveFile { path })?;
+    }
+
+    Ok(())
+  }
+
+  pub fn save(&self, content: &Rope, cursor: usize) -> Result {
+    fs::create_dir_all(&self.directory).context(error::CreateDirectory {
+      path: &self.directory,
+    })?;
+
+    atomic_write(&self.content_path(), content.to_string().as_bytes())?;
+
+    atomic_write(&self.cursor_path(), cursor.to_string().as_bytes())?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  #[test]
+  fn load_empty() {
+    let tempdir = TempDir::new().unwrap();
+
+    let scratch = Scratch::new(tempdir.path().join("scratch"));
+
+    let (content, cursor) = scratch.load().unwrap();
+
+    assert_eq!(content.to_string(), "");
+    assert_eq!(cursor, 0);
+    assert!(scratch.history().unwrap().is_empty());
+  }
+
+  #[test]
+  fn save_and_load() {
+    let tempdir = TempDir::new().unwrap();
+
+    let scratch = Scratch::new(tempdir.path().join("scratch"));
+
+    scratch.save(&Rope::from_str("hello"), 3).unwrap();
+
+    let (content, cursor) = scratch.load().unwrap();
+
+    assert_eq!(content.to_string(), "hello");
+    assert_eq!(cursor, 3);
+  }
+
+  #[test]
+  fn cursor_is_clamped() {
+    let tempdir = TempDir::new().unwrap();
+
+    let scratch = Scratch::new(tempdir.path().join("scratch"));
+
+    scratch.save(&Rope::from_str("hello"), 100).unwrap();
+
+    assert_eq!(scratch.load().unwrap().1, 5);
+  }
+
+  #[test]
+  fn history() {
+    let tempdir = TempDir::new().unwrap();
+
+    let scratch = Scratch::new(tempdir.path().join("scratch"));
+
+    scratch.save(&Rope::from_str("foo"), 0).unwrap();
+    scratch.load().unwrap();
+    scratch.load().unwrap();
+
+    scratch.save(&Rope::from_str("bar"), 0).unwrap();
+    scratch.load().unwrap();
+
+    let history = scratch
+      .history()
+      .unwrap()
+      .iter()
+      .map(|path| fs::read_to_string(path).unwrap())
+      .collect::<Vec<String>>();
+
+    assert_eq!(history, ["foo", "bar"]);
+  }
+
+  #[test]
+  fn history_is_pruned() {
+    let tempdir = TempDir::new().unwrap();
+
+    let scratch = Scratch::new(tempdir.path().join("scratch"));
+
+    for i in 0..HISTORY_LIMIT + 5 {
+      scratch.save(&Rope::from_str(&i.to_string()), 0).unwrap();
+      scratch.load().unwrap();
+    }
+
+    let history = scratch.history().unwrap();
+
+    assert_eq!(history.len(), HISTORY_LIMIT);
+
+    assert_eq!(
+      fs::read_to_string(history.last().unwrap()).unwrap(),
+      (HISTORY_LIMIT + 4).to_string()
+    );
+
+    assert_eq!(fs::read_to_string(&history[0]).unwrap(), "5");
+  }
+}
@@ -0,0 +1,126 @@
+use super::*;
+
+/// A cursor and the text selected with it, which runs from `anchor` to
+/// `head`. The selection is empty when they are the same.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Selection {
+  pub anchor: usize,
+  /// Visual column that moving up and down aims for, kept while passing
+  /// through lines too short to reach it.
+  #[serde(skip)]
+  pub goal: Option<usize>,
+  pub head: usize,
+}
+
+impl Selection {
+  pub fn cursor(char_idx: usize) -> Self {
+    Self::new(char_idx, char_idx)
+  }
+
+  pub fn new(anchor: usize, head: usize) -> Self {
+    Self {
+      anchor,
+      goal: None,
+      head,
+    }
+  }
+
+  pub fn end(self) -> usize {
+    self.anchor.max(self.head)
+  }
+
+  pub fn is_empty(self) -> bool {
+    self.anchor == self.head
+  }
+
+  /// Sort `selections` and merge those that overlap, or that are cursors at
+  /// the same place or at the edge of another selection. Returns the merged
+  /// selections and the index of the one that `selections[main]` was merged
+  /// into.
+  pub fn merge(selections: &[Self], main: usize) -> (Vec<Self>, usize) {
+    let mut order = (0..selections.len()).collect::<Vec<usize>>();
+
+    order.sort_by_key(|&i| (selections[i].start(), selections[i].end()));
+
+    let mut merged = Vec::<Self>::new();
+
+    let mut merged_main = 0;
+
+    for i in order {
+      let selection = selections[i];
+
+      match merged.last_mut() {
+        Some(last) if last.overlaps(selection) => {
+          let (start, end) = (last.start(), selection.end().max(last.end()));
+
+          *last = Self {
+            goal: last.goal,
+            ..if last.head < last.anchor {
+              Self::new(end, start)
+            } else {
+              Self::new(start, end)
+            }
+          };
+        }
+        _ => merged.push(selection),
+      }
+
+      if i == main {
+        merged_main = merged.len() - 1;
+      }
+    }
+
+    (merged, merged_main)
+  }
+
+  /// Whether `other`, which starts no earlier, should be merged with this
+  /// selection.
+  fn overlaps(self, other: Self) -> bool {
+    other.start() < self.end()
+      || other.start() == self.start()
+      || (other.start() == self.end() && (self.is_empty() || other.is_empty()))
+  }
+
+  pub fn range(self) -> Range<usize> {
+    self.start()..self.end()
+  }
+
+  pub fn start(self) -> usize {
+    self.anchor.min(self.head)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn merge() {
+    assert_eq!(
+      Selection::merge(
+        &[
+          Selection::cursor(5),
+          Selection::cursor(1),
+          Selection::cursor(5),
+        ],
+        2
+      ),
+      (vec![Selection::cursor(1), Selection::cursor(5)], 1)
+    );
+
+    assert_eq!(
+      Selection::merge(&[Selection::new(4, 2), Selection::new(3, 6)], 1),
+      (vec![Selection::new(6, 2)], 0)
+    );
+
+    assert_eq!(
+      Selection::merge(&[Selection::new(0, 2), Selection::cursor(2)], 0),
+      (vec![Selection::new(0, 2)], 0)
+    );
+
+    assert_eq!(
+      Selection::merge(&[Selection::new(0, 2), Selection::new(2, 4)], 1),
+      (vec![Selection::new(0, 2), Selection::new(2, 4)], 1)
+    );
+  }
+}
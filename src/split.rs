@@ -0,0 +1,217 @@
+use super::*;
+
+/// How the text area is divided between panes, as a binary tree whose leaves
+/// are pane IDs.
+#[derive(Debug, PartialEq)]
+pub enum Split {
+  Leaf(u64),
+  Node {
+    first: Box<Split>,
+    orientation: Orientation,
+    /// Fraction of the area given to `first`.
+    ratio: f32,
+    second: Box<Split>,
+  },
+}
+
+impl Split {
+  fn contains(&self, pane: u64) -> bool {
+    match self {
+      Self::Leaf(id) => *id == pane,
+      Self::Node { first, second, .. } => {
+        first.contains(pane) || second.contains(pane)
+      }
+    }
+  }
+
+  fn first_pane(&self) -> u64 {
+    match self {
+      Self::Leaf(id) => *id,
+      Self::Node { first, .. } => first.first_pane(),
+    }
+  }
+
+  /// Area of each pane when the split fills `area`, from top left to bottom
+  /// right.
+  pub fn layout(&self, area: Rect) -> Vec<(u64, Rect)> {
+    let mut panes = Vec::new();
+    self.layout_into(area, &mut panes);
+    panes
+  }
+
+  fn layout_into(&self, area: Rect, panes: &mut Vec<(u64, Rect)>) {
+    match self {
+      Self::Leaf(id) => panes.push((*id, area)),
+      Self::Node {
+        first,
+        orientation,
+        ratio,
+        second,
+      } => {
+        let (a, b) = area.split(*orientation, *ratio);
+        first.layout_into(a, panes);
+        second.layout_into(b, panes);
+      }
+    }
+  }
+
+  /// Remove `pane`, giving its area to its sibling, and return the pane that
+  /// should be focused in its place. The last pane cannot be removed.
+  pub fn remove(&mut self, pane: u64) -> Option<u64> {
+    let Self::Node { first, second, .. } = self else {
+      return None;
+    };
+
+    let sibling = if **first == Self::Leaf(pane) {
+      mem::replace(&mut **second, Self::Leaf(pane))
+    } else if **second == Self::Leaf(pane) {
+      mem::replace(&mut **first, Self::Leaf(pane))
+    } else {
+      return first.remove(pane).or_else(|| second.remove(pane));
+    };
+
+    *self = sibling;
+
+    Some(self.first_pane())
+  }
+
+  /// Move the divider of the innermost split with `orientation` around `pane`
+  /// by `delta`, as a fraction of the split's area.
+  pub fn resize(
+    &mut self,
+    pane: u64,
+    orientation: Orientation,
+    delta: f32,
+  ) -> bool {
+    let Self::Node {
+      first,
+      orientation: own,
+      ratio,
+      second,
+    } = self
+    else {
+      return false;
+    };
+
+    if first.resize(pane, orientation, delta)
+      || second.resize(pane, orientation, delta)
+    {
+      return true;
+    }
+
+    if *own == orientation && (first.contains(pane) || second.contains(pane)) {
+      *ratio = (*ratio + delta).clamp(0.1, 0.9);
+      return true;
+    }
+
+    false
+  }
+
+  /// Divide the area of `pane` evenly between it and `new`, which goes below
+  /// or to the right.
+  pub fn split(&mut self, pane: u64, orientation: Orientation, new: u64) {
+    match self {
+      Self::Leaf(id) if *id == pane => {
+        *self = Self::Node {
+          first: Box::new(Self::Leaf(pane)),
+          orientation,
+          ratio: 0.5,
+          second: Box::new(Self::Leaf(new)),
+        };
+      }
+      Self::Leaf(_) => {}
+      Self::Node { first, second, .. } => {
+        first.split(pane, orientation, new);
+        second.split(pane, orientation, new);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const AREA: Rect = Rect {
+    height: 100.0,
+    width: 200.0,
+    x: 0.0,
+    y: 0.0,
+  };
+
+  fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    Rect {
+      height,
+      width,
+      x,
+      y,
+    }
+  }
+
+  #[test]
+  fn split_and_layout() {
+    let mut split = Split::Leaf(0);
+
+    assert_eq!(split.layout(AREA), [(0, AREA)]);
+
+    split.split(0, Orientation::Vertical, 1);
+    split.split(1, Orientation::Horizontal, 2);
+
+    assert_eq!(
+      split.layout(AREA),
+      [
+        (0, rect(0.0, 0.0, 100.0, 100.0)),
+        (1, rect(100.0, 0.0, 100.0, 50.0)),
+        (2, rect(100.0, 50.0, 100.0, 50.0)),
+      ]
+    );
+  }
+
+  #[test]
+  fn remove() {
+    let mut split = Split::Leaf(0);
+
+    assert_eq!(split.remove(0), None);
+
+    split.split(0, Orientation::Vertical, 1);
+    split.split(1, Orientation::Horizontal, 2);
+    split.split(1, Orientation::Vertical, 3);
+
+    assert_eq!(split.remove(2), Some(1));
+
+    assert_eq!(
+      split.layout(AREA),
+      [
+        (0, rect(0.0, 0.0, 100.0, 100.0)),
+        (1, rect(100.0, 0.0, 50.0, 100.0)),
+        (3, rect(150.0, 0.0, 50.0, 100.0)),
+      ]
+    );
+
+    assert_eq!(split.remove(0), Some(1));
+    assert_eq!(split.remove(3), Some(1));
+    assert_eq!(split, Split::Leaf(1));
+  }
+
+  #[test]
+  fn resize() {
+    let mut split = Split::Leaf(0);
+
+    split.split(0, Orientation::Vertical, 1);
+    split.split(1, Orientation::Horizontal, 2);
+
+    assert!(!split.resize(0, Orientation::Horizontal, 0.1));
+    assert!(split.resize(2, Orientation::Vertical, 0.25));
+    assert!(split.resize(2, Orientation::Horizontal, -0.25));
+    assert!(split.resize(2, Orientation::Horizontal, -0.25));
+
+    assert_eq!(
+      split.layout(AREA),
+      [
+        (0, rect(0.0, 0.0, 150.0, 100.0)),
+        (1, rect(150.0, 0.0, 50.0, 10.0)),
+        (2, rect(150.0, 10.0, 50.0, 90.0)),
+      ]
+    );
+  }
+}
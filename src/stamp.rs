@@ -0,0 +1,21 @@
+use super::*;
+
+/// Size and modification time of a file, used to tell whether it has changed
+/// on disk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stamp {
+  len: u64,
+  modified: Option<SystemTime>,
+}
+
+impl Stamp {
+  /// Stamp of the file at `path`, or `None` if it does not exist.
+  pub fn of(path: &Path) -> Option<Self> {
+    let metadata = fs::metadata(path).ok()?;
+
+    Some(Self {
+      len: metadata.len(),
+      modified: metadata.modified().ok(),
+    })
+  }
+}
@@ -0,0 +1,469 @@
+use super::*;
+
+const EXTENSION: &str = "swp";
+
+const INTERVAL: Duration = Duration::from_secs(2);
+
+const PANIC_LOCK_ATTEMPTS: usize = 100;
+
+/// Contents of a modified buffer, along with the file it belongs to, if any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+  pub content: Rope,
+  pub path: Option<PathBuf>,
+}
+
+impl Snapshot {
+  fn deserialize(bytes: &[u8]) -> Option<Self> {
+    let text = String::from_utf8(bytes.to_vec()).ok()?;
+
+    let (path, content) = text.split_once('\n')?;
+
+    Some(Self {
+      content: Rope::from_str(content),
+      path: (!path.is_empty()).then(|| PathBuf::from(path)),
+    })
+  }
+
+  fn serialize(&self) -> Vec<u8> {
+    let mut bytes = self
+      .path
+      .as_ref()
+      .map(|path| path.to_string_lossy().into_owned())
+      .unwrap_or_default()
+      .into_bytes();
+
+    bytes.push(b'\n');
+
+    for chunk in self.content.chunks() {
+      bytes.extend_from_slice(chunk.as_bytes());
+    }
+
+    bytes
+  }
+}
+
+/// A swap file left behind by a session that did not exit cleanly.
+#[derive(Debug)]
+pub struct Recovery {
+  pub path: PathBuf,
+  pub snapshot: Snapshot,
+}
+
+#[derive(Default)]
+struct State {
+  current: BTreeMap<PathBuf, Snapshot>,
+  panicked: bool,
+  pending: BTreeMap<PathBuf, Option<Snapshot>>,
+}
+
+impl State {
+  fn lock(state: &Mutex<Self>) -> MutexGuard<'_, Self> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+/// Writes swap files for modified buffers on a background thread.
+///
+/// Each session holds an exclusive lock on `<session>.lock` in the swap
+/// directory for as long as it runs, and names its swap files
+/// `<session>-<id>.swp`. Swap files whose owner no longer holds its lock were
+/// left behind by a crash and can be recovered.
+///
+/// Sessions are named by process ID and start time, since process IDs are
+/// reused, and a session must not mistake a crashed session's swap files for
+/// its own.
+pub struct Swap {
+  directory: PathBuf,
+  lock: Option<(File, PathBuf)>,
+  session: String,
+  shutdown: Option<mpsc::Sender<()>>,
+  state: Arc<Mutex<State>>,
+  thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Swap {
+  pub fn new(directory: PathBuf) -> Result<Self> {
+    fs::create_dir_all(&directory)
+      .context(error::CreateDirectory { path: &directory })?;
+
+    let session = format!(
+      "{}-{:x}",
+      process::id(),
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+    );
+
+    let lock_path = directory.join(format!("{session}.lock"));
+
+    let lock = File::create(&lock_path)
+      .and_then(|file| file.lock().map(|()| file))
+      .context(error::LockFile { path: &lock_path })?;
+
+    let state = Arc::new(Mutex::new(State::default()));
+
+    let (shutdown, receiver) = mpsc::channel();
+
+    let thread = thread::spawn({
+      let state = state.clone();
+      move || Self::run(&state, &receiver)
+    });
+
+    Ok(Self {
+      directory,
+      lock: Some((lock, lock_path)),
+      session,
+      shutdown: Some(shutdown),
+      state,
+      thread: Some(thread),
+    })
+  }
+
+  fn run(state: &Mutex<State>, shutdown: &mpsc::Receiver<()>) {
+    loop {
+      let stop = !matches!(
+        shutdown.recv_timeout(INTERVAL),
+        Err(mpsc::RecvTimeoutError::Timeout)
+      );
+
+      let pending = {
+        let mut state = State::lock(state);
+
+        if state.panicked {
+          return;
+        }
+
+        mem::take(&mut state.pending)
+      };
+
+      for (path, snapshot) in pending {
+        let result = match snapshot {
+          Some(snapshot) => atomic_write(&path, &snapshot.serialize()),
+          None => match fs::remove_file(&path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => {
+              Err(error).context(error::RemoveFile { path: &path })
+            }
+            _ => Ok(()),
+          },
+        };
+
+        if let Err(error) = result {
+          log::warn!("swap: {}", error.summary());
+        }
+      }
+
+      if stop {
+        return;
+      }
+    }
+  }
+
+  /// Install a panic hook which writes every modified buffer to its swap file
+  /// before running the previously installed hook.
+  pub fn install_panic_hook(&self) {
+    let state = Arc::downgrade(&self.state);
+
+    let hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+      if let Some(state) = state.upgrade() {
+        Self::flush(&state);
+      }
+
+      hook(info);
+    }));
+  }
+
+  /// Write every snapshot and carry out pending removals on the current
+  /// thread, and stop the swap thread. The swap thread only holds the lock
+  /// briefly, so wait a little for it, but give up rather than deadlock if
+  /// the lock is held by the panicking thread itself.
+  fn flush(state: &Mutex<State>) {
+    let mut state = (0..PANIC_LOCK_ATTEMPTS).find_map(|attempt| {
+      if attempt > 0 {
+        thread::sleep(Duration::from_millis(1));
+      }
+
+      match state.try_lock() {
+        Ok(state) => Some(state),
+        Err(TryLockError::Poisoned(error)) => Some(error.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+      }
+    });
+
+    let Some(state) = &mut state else {
+      eprintln!("unsaved changes could not be written to swap files");
+      return;
+    };
+
+    state.panicked = true;
+
+    for (path, snapshot) in mem::take(&mut state.pending) {
+      if snapshot.is_none() {
+        fs::remove_file(path).ok();
+      }
+    }
+
+    for (path, snapshot) in &state.current {
+      if fs::write(path, snapshot.serialize()).is_ok() {
+        eprintln!("unsaved changes written to `{}`", path.display());
+      }
+    }
+  }
+
+  /// Path of the swap file for the buffer with the given `id`.
+  pub fn path(&self, id: u64) -> PathBuf {
+    self
+      .directory
+      .join(format!("{}-{id}.{EXTENSION}", self.session))
+  }
+
+  /// Swap files which were left behind by other sessions that are no longer
+  /// running.
+  pub fn recoverable(&self) -> Vec<Recovery> {
+    let Ok(entries) = fs::read_dir(&self.directory) else {
+      return Vec::new();
+    };
+
+    let mut alive = BTreeMap::new();
+
+    let mut recoveries = Vec::new();
+
+    for entry in entries.flatten() {
+      let path = entry.path();
+
+      if path.extension() != Some(EXTENSION.as_ref()) {
+        continue;
+      }
+
+      let Some(session) = path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .and_then(|stem| stem.rsplit_once('-'))
+        .map(|(session, _)| session.to_owned())
+      else {
+        continue;
+      };
+
+      if session == self.session {
+        continue;
+      }
+
+      let alive = *alive
+        .entry(session.clone())
+        .or_insert_with(|| self.is_alive(&session));
+
+      if alive {
+        continue;
+      }
+
+      if let Some(snapshot) = fs::read(&path)
+        .ok()
+        .and_then(|bytes| Snapshot::deserialize(&bytes))
+      {
+        recoveries.push(Recovery { path, snapshot });
+      }
+    }
+
+    recoveries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    recoveries
+  }
+
+  fn is_alive(&self, session: &str) -> bool {
+    let path = self.directory.join(format!("{session}.lock"));
+
+    let Ok(file) = File::open(&path) else {
+      return false;
+    };
+
+    match file.try_lock() {
+      Ok(()) => {
+        drop(file);
+        fs::remove_file(path).ok();
+        false
+      }
+      Err(_) => true,
+    }
+  }
+
+  /// Delete the swap file at `path`.
+  pub fn remove(&self, path: &Path) {
+    let mut state = State::lock(&self.state);
+    state.current.remove(path);
+    state.pending.insert(path.into(), None);
+  }
+
+  /// Schedule `snapshot` to be written to the swap file at `path`.
+  pub fn update(&self, path: &Path, snapshot: Snapshot) {
+    let mut state = State::lock(&self.state);
+    state.current.insert(path.into(), snapshot.clone());
+    state.pending.insert(path.into(), Some(snapshot));
+  }
+}
+
+impl Drop for Swap {
+  fn drop(&mut self) {
+    self.shutdown.take();
+
+    if let Some(thread) = self.thread.take() {
+      thread.join().ok();
+    }
+
+    if let Some((file, path)) = self.lock.take() {
+      drop(file);
+      fs::remove_file(path).ok();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  fn snapshot(content: &str, path: Option<&str>) -> Snapshot {
+    Snapshot {
+      content: Rope::from_str(content),
+      path: path.map(PathBuf::from),
+    }
+  }
+
+  #[test]
+  fn serialization_round_trip() {
+    for snapshot in [
+      snapshot("", None),
+      snapshot("foo\nbar\n", None),
+      snapshot("foo\nbar", Some("/tmp/foo.txt")),
+    ] {
+      assert_eq!(Snapshot::deserialize(&snapshot.serialize()), Some(snapshot));
+    }
+  }
+
+  #[test]
+  fn update_and_remove() {
+    let tempdir = TempDir::new().unwrap();
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    let path = swap.path(0);
+
+    swap.update(&path, snapshot("foo", None));
+
+    drop(swap);
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "\nfoo");
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    swap.remove(&path);
+
+    drop(swap);
+
+    assert!(!path.exists());
+  }
+
+  #[test]
+  fn recover_from_dead_session() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("1-0.swp");
+
+    fs::write(&path, snapshot("foo", Some("foo.txt")).serialize()).unwrap();
+
+    fs::write(tempdir.path().join("1.lock"), "").unwrap();
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    let recoveries = swap.recoverable();
+
+    assert_eq!(recoveries.len(), 1);
+    assert_eq!(recoveries[0].path, path);
+    assert_eq!(recoveries[0].snapshot, snapshot("foo", Some("foo.txt")));
+
+    assert!(!tempdir.path().join("1.lock").exists());
+  }
+
+  #[test]
+  fn ignore_live_session() {
+    let tempdir = TempDir::new().unwrap();
+
+    let lock = tempdir.path().join("1.lock");
+
+    fs::write(&lock, "").unwrap();
+
+    let file = File::open(&lock).unwrap();
+
+    file.lock().unwrap();
+
+    fs::write(tempdir.path().join("1-0.swp"), "\nbar").unwrap();
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    fs::write(swap.path(0), snapshot("foo", None).serialize()).unwrap();
+
+    assert!(swap.recoverable().is_empty());
+  }
+
+  #[test]
+  fn recover_from_dead_session_with_same_pid() {
+    let tempdir = TempDir::new().unwrap();
+
+    let session = format!("{}-0", process::id());
+
+    let path = tempdir.path().join(format!("{session}-0.swp"));
+
+    fs::write(&path, snapshot("foo", None).serialize()).unwrap();
+
+    fs::write(tempdir.path().join(format!("{session}.lock")), "").unwrap();
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    assert_ne!(swap.path(0), path);
+
+    let recoveries = swap.recoverable();
+
+    assert_eq!(recoveries.len(), 1);
+    assert_eq!(recoveries[0].path, path);
+  }
+
+  #[test]
+  fn panic_writes_pending_snapshots() {
+    let tempdir = TempDir::new().unwrap();
+
+    let swap = Swap::new(tempdir.path().into()).unwrap();
+
+    swap.install_panic_hook();
+
+    let path = swap.path(0);
+
+    swap.update(&path, snapshot("foo", None));
+
+    let removed = tempdir.path().join("removed.swp");
+
+    fs::write(&removed, "").unwrap();
+
+    swap.remove(&removed);
+
+    let (sender, locked) = mpsc::channel();
+
+    let holder = thread::spawn({
+      let state = swap.state.clone();
+      move || {
+        let _state = State::lock(&state);
+        sender.send(()).unwrap();
+        thread::sleep(Duration::from_millis(20));
+      }
+    });
+
+    locked.recv().unwrap();
+
+    thread::spawn(|| panic!("crash")).join().unwrap_err();
+
+    holder.join().unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "\nfoo");
+    assert!(!removed.exists());
+  }
+}
@@ -0,0 +1,56 @@
+use super::*;
+
+#[cfg(target_os = "linux")]
+use arboard::{GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+/// The clipboard of the windowing system.
+pub struct SystemClipboard {
+  clipboard: arboard::Clipboard,
+}
+
+impl SystemClipboard {
+  pub fn new() -> Result<Self> {
+    Ok(Self {
+      clipboard: arboard::Clipboard::new().context(error::OpenClipboard)?,
+    })
+  }
+
+  fn text(result: Result<String, arboard::Error>) -> Result<Option<String>> {
+    match result {
+      Ok(text) => Ok(Some(text)),
+      Err(arboard::Error::ContentNotAvailable) => Ok(None),
+      Err(error) => Err(error).context(error::ReadClipboard),
+    }
+  }
+}
+
+impl Clipboard for SystemClipboard {
+  fn get(&mut self) -> Result<Option<String>> {
+    Self::text(self.clipboard.get_text())
+  }
+
+  fn set(&mut self, text: String) -> Result {
+    self.clipboard.set_text(text).context(error::WriteClipboard)
+  }
+
+  #[cfg(target_os = "linux")]
+  fn get_primary(&mut self) -> Result<Option<String>> {
+    Self::text(
+      self
+        .clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text(),
+    )
+  }
+
+  #[cfg(target_os = "linux")]
+  fn set_primary(&mut self, text: String) -> Result {
+    self
+      .clipboard
+      .set()
+      .clipboard(LinuxClipboardKind::Primary)
+      .text(text)
+      .context(error::WriteClipboard)
+  }
+}
@@ -0,0 +1,55 @@
+/// A tab in the tab bar along the top of the window, one per buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tab {
+  pub active: bool,
+  pub modified: bool,
+  pub name: String,
+}
+
+impl Tab {
+  pub const HEIGHT: f32 = 44.0;
+
+  const MAX_WIDTH: f32 = 240.0;
+
+  /// Index of the tab under `x`, clamped to the last tab, when `count` tabs
+  /// share a window `window_width` pixels wide.
+  pub fn index_at(x: f32, count: usize, window_width: f32) -> usize {
+    ((x.max(0.0) / Self::width(count, window_width)) as usize)
+      .min(count.saturating_sub(1))
+  }
+
+  pub fn label(&self) -> String {
+    if self.modified {
+      format!("*{}", self.name)
+    } else {
+      self.name.clone()
+    }
+  }
+
+  /// Width of each tab when `count` tabs share a window `window_width` pixels
+  /// wide. Tabs shrink to fit rather than scrolling.
+  pub fn width(count: usize, window_width: f32) -> f32 {
+    (window_width / count.max(1) as f32).min(Self::MAX_WIDTH)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn width() {
+    assert_eq!(Tab::width(0, 1000.0), 240.0);
+    assert_eq!(Tab::width(2, 1000.0), 240.0);
+    assert_eq!(Tab::width(10, 1000.0), 100.0);
+  }
+
+  #[test]
+  fn index_at() {
+    assert_eq!(Tab::index_at(-5.0, 3, 1000.0), 0);
+    assert_eq!(Tab::index_at(239.0, 3, 1000.0), 0);
+    assert_eq!(Tab::index_at(240.0, 3, 1000.0), 1);
+    assert_eq!(Tab::index_at(900.0, 3, 1000.0), 2);
+    assert_eq!(Tab::index_at(150.0, 10, 1000.0), 1);
+  }
+}
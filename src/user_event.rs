@@ -0,0 +1,9 @@
+use super::*;
+
+/// Events sent to the event loop from background threads.
+#[derive(Debug)]
+pub enum UserEvent {
+  FileChanged(PathBuf),
+  FinderDone { generation: u64 },
+  FinderPaths { generation: u64, paths: Vec<String> },
+}
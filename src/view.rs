@@ -0,0 +1,55 @@
+use super::*;
+
+/// Everything drawn in a frame, built by the app and handed to the renderer.
+#[derive(Debug, PartialEq)]
+pub struct View {
+  pub finder: Option<FinderView>,
+  pub indicators: String,
+  pub panes: Vec<PaneView>,
+  pub status_line: Option<String>,
+  pub tabs: Vec<Tab>,
+}
+
+impl View {
+  /// Height of the status line along the bottom of the window.
+  pub const STATUS_HEIGHT: f32 = 60.0;
+}
+
+/// The visible part of a pane's buffer.
+#[derive(Debug, PartialEq)]
+pub struct PaneView {
+  /// Row and column of the cursor within `lines`, if it is visible.
+  pub cursor: Option<(usize, usize)>,
+  pub focused: bool,
+  pub lines: Vec<String>,
+  /// Rows and columns of the other visible cursors, when editing with more
+  /// than one.
+  pub other_cursors: Vec<(usize, usize)>,
+  /// Row and column range of the text being composed with an input method,
+  /// which is shown at the cursor but is not yet in the buffer.
+  pub preedit: Option<(usize, Range<usize>)>,
+  pub rect: Rect,
+  /// Distance in pixels that the lines are scrolled up and left, partway
+  /// into the first of them.
+  pub scroll_offset: (f32, f32),
+  /// Row and column range of the selected text on each visible line. The
+  /// range ends one past the end of the line if the selection includes its
+  /// line ending.
+  pub selection: Vec<(usize, Range<usize>)>,
+}
+
+/// The quick open overlay.
+#[derive(Debug, PartialEq)]
+pub struct FinderView {
+  /// Number of matches and of files found so far.
+  pub count: String,
+  pub matches: Vec<String>,
+  pub query: String,
+  /// Index of the selected match within `matches`.
+  pub selected: usize,
+}
+
+impl FinderView {
+  /// Number of matches shown at once.
+  pub const ROWS: usize = 12;
+}
@@ -0,0 +1,138 @@
+use super::*;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+type Watched = Arc<Mutex<BTreeMap<PathBuf, Option<Stamp>>>>;
+
+/// Polls watched files on a background thread, and calls `notify` with the
+/// path of any file whose size or modification time changes.
+pub struct Watcher {
+  shutdown: Option<mpsc::Sender<()>>,
+  thread: Option<thread::JoinHandle<()>>,
+  watched: Watched,
+}
+
+impl Watcher {
+  pub fn new(notify: impl Fn(PathBuf) + Send + 'static) -> Self {
+    let watched = Watched::default();
+
+    let (shutdown, receiver) = mpsc::channel();
+
+    let thread = thread::spawn({
+      let watched = watched.clone();
+      move || Self::run(&watched, &receiver, &notify)
+    });
+
+    Self {
+      shutdown: Some(shutdown),
+      thread: Some(thread),
+      watched,
+    }
+  }
+
+  fn run(
+    watched: &Mutex<BTreeMap<PathBuf, Option<Stamp>>>,
+    shutdown: &mpsc::Receiver<()>,
+    notify: &dyn Fn(PathBuf),
+  ) {
+    while let Err(mpsc::RecvTimeoutError::Timeout) =
+      shutdown.recv_timeout(INTERVAL)
+    {
+      let paths = watched
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .keys()
+        .cloned()
+        .collect::<Vec<PathBuf>>();
+
+      for path in paths {
+        let stamp = Stamp::of(&path);
+
+        let changed = watched
+          .lock()
+          .unwrap_or_else(PoisonError::into_inner)
+          .get_mut(&path)
+          .is_some_and(|previous| mem::replace(previous, stamp) != stamp);
+
+        if changed {
+          notify(path);
+        }
+      }
+    }
+  }
+
+  pub fn unwatch(&self, path: &Path) {
+    self
+      .watched
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .remove(path);
+  }
+
+  pub fn watch(&self, path: &Path) {
+    self
+      .watched
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .insert(path.into(), Stamp::of(path));
+  }
+}
+
+impl Drop for Watcher {
+  fn drop(&mut self) {
+    self.shutdown.take();
+
+    if let Some(thread) = self.thread.take() {
+      thread.join().ok();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, tempfile::TempDir};
+
+  #[test]
+  fn notify_on_change() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    fs::write(&path, "foo").unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+
+    let watcher = Watcher::new(move |path| sender.send(path).unwrap());
+
+    watcher.watch(&path);
+
+    fs::write(&path, "foobar").unwrap();
+
+    assert_eq!(receiver.recv_timeout(INTERVAL * 5).unwrap(), path);
+
+    assert!(receiver.recv_timeout(INTERVAL * 2).is_err());
+
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(receiver.recv_timeout(INTERVAL * 5).unwrap(), path);
+  }
+
+  #[test]
+  fn unwatch() {
+    let tempdir = TempDir::new().unwrap();
+
+    let path = tempdir.path().join("foo.txt");
+
+    let (sender, receiver) = mpsc::channel();
+
+    let watcher = Watcher::new(move |path| sender.send(path).unwrap());
+
+    watcher.watch(&path);
+
+    watcher.unwatch(&path);
+
+    fs::write(&path, "foo").unwrap();
+
+    assert!(receiver.recv_timeout(INTERVAL * 2).is_err());
+  }
+}
@@ -0,0 +1,94 @@
+use {super::*, unicode_segmentation::UnicodeSegmentation};
+
+/// Characters that separate words by default, along with whitespace.
+pub const WORD_SEPARATORS: &str = "`~!@#$%^&*()-=+[{]}\\|;:'\",.<>/?";
+
+/// Char ranges of the words in `text`, where word motion and deletion stop.
+/// Words are found by Unicode word segmentation, and split further around
+/// each of `separators`. A run of punctuation counts as one word, and
+/// whitespace is left out.
+pub fn word_ranges(text: &str, separators: &str) -> Vec<Range<usize>> {
+  let is_separator = |c: char| separators.contains(c);
+
+  let mut ranges = Vec::<Range<usize>>::new();
+
+  // Whether the last range is punctuation that the next range continues, if
+  // it is punctuation too.
+  let mut punctuation = false;
+
+  let mut start = 0;
+
+  for segment in text.split_word_bounds() {
+    let chars = segment.chars().collect::<Vec<char>>();
+
+    if chars.iter().all(|c| c.is_whitespace()) {
+      punctuation = false;
+      start += chars.len();
+      continue;
+    }
+
+    for piece in chars.chunk_by(|&a, &b| !is_separator(a) && !is_separator(b)) {
+      let end = start + piece.len();
+
+      let word =
+        !is_separator(piece[0]) && piece.iter().any(|c| c.is_alphanumeric());
+
+      match ranges.last_mut() {
+        Some(last) if punctuation && !word => last.end = end,
+        _ => ranges.push(start..end),
+      }
+
+      punctuation = !word;
+      start = end;
+    }
+  }
+
+  ranges
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[track_caller]
+  fn case(text: &str, separators: &str, expected: &[&str]) {
+    let chars = text.chars().collect::<Vec<char>>();
+
+    assert_eq!(
+      word_ranges(text, separators)
+        .into_iter()
+        .map(|range| chars[range].iter().collect::<String>())
+        .collect::<Vec<String>>(),
+      expected,
+    );
+  }
+
+  #[test]
+  fn words() {
+    case("  foo bar\n", WORD_SEPARATORS, &["foo", "bar"]);
+    case(
+      "foo.bar(baz);",
+      WORD_SEPARATORS,
+      &["foo", ".", "bar", "(", "baz", ");"],
+    );
+    case("foo.bar", "", &["foo.bar"]);
+    case("a -> b", WORD_SEPARATORS, &["a", "->", "b"]);
+    case(
+      "snake_case camelCase",
+      WORD_SEPARATORS,
+      &["snake_case", "camelCase"],
+    );
+    case(
+      "日本語 über naïve",
+      WORD_SEPARATORS,
+      &["日", "本", "語", "über", "naïve"],
+    );
+    case(
+      "e\u{301}t\u{e9} 3.14",
+      WORD_SEPARATORS,
+      &["e\u{301}t\u{e9}", "3", ".", "14"],
+    );
+    case("a/b", "", &["a", "/", "b"]);
+    case("", WORD_SEPARATORS, &[]);
+  }
+}